@@ -0,0 +1,56 @@
+//! Compares the magic bitboard attack tables against walking the rays square by square.
+//!
+//! Run with `cargo run --release --example attack_bench`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use skaki_chess::board::attacks::{bishop_attacks, bishop_attacks_naive, init, rook_attacks, rook_attacks_naive};
+use skaki_chess::square::Square;
+use test_rng::TestRng;
+
+// The same generator the randomized tests of the library use.
+#[allow(dead_code)]
+#[path = "../src/board/test_rng.rs"]
+mod test_rng;
+
+const ROUNDS: usize = 200;
+
+fn main() {
+    let start = Instant::now();
+    init();
+    println!("Building the attack tables took {:?}", start.elapsed());
+
+    // A fixed set of occupancies, from nearly empty to nearly full boards.
+    let mut rng = TestRng::new(0x2545_f491_4f6c_dd1d);
+    let occupancies: Vec<u64> = (0..256).map(|i| match i % 3 {
+        0 => rng.next_u64() & rng.next_u64(),
+        1 => rng.next_u64(),
+        _ => rng.next_u64() | rng.next_u64(),
+    }).collect();
+    let squares: Vec<Square> = (0..8).flat_map(|row| (0..8).map(move |column| Square::new(row, column))).collect();
+
+    let lookups = ROUNDS * occupancies.len() * squares.len();
+    let run = |attacks: fn(Square, u64) -> u64| {
+        let start = Instant::now();
+        let mut total = 0u64;
+        for _ in 0..ROUNDS {
+            for &occupied in &occupancies {
+                for &square in &squares {
+                    total ^= attacks(black_box(square), black_box(occupied));
+                }
+            }
+        }
+        black_box(total);
+        start.elapsed()
+    };
+
+    report("rook, magic", run(rook_attacks), lookups);
+    report("rook, naive", run(rook_attacks_naive), lookups);
+    report("bishop, magic", run(bishop_attacks), lookups);
+    report("bishop, naive", run(bishop_attacks_naive), lookups);
+}
+
+fn report(name: &str, elapsed: Duration, lookups: usize) {
+    let nanos = elapsed.as_nanos() as f64 / lookups as f64;
+    println!("{name:>14}: {elapsed:>10.2?} for {lookups} lookups, {nanos:.2} ns per lookup");
+}
@@ -0,0 +1,330 @@
+use std::sync::OnceLock;
+use crate::square::Square;
+use crate::standard::piece::PieceColor;
+
+// Attack tables for the pieces of standard chess on an 8x8 bitboard, see `BitboardBoard` for the square layout.
+// Sliding pieces use magic bitboards: the relevant blockers of a square are multiplied by a magic number, and the
+// top bits of the product index a table with the attacks for exactly that set of blockers. The magic numbers are
+// searched for the first time the tables are used, with a fixed seed so the tables are the same on every run.
+
+const ROOK_DIRECTIONS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+const BISHOP_DIRECTIONS: [(i32, i32); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+const KNIGHT_OFFSETS: [(i32, i32); 8] = [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)];
+const KING_OFFSETS: [(i32, i32); 8] = [(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)];
+
+/// The seed of the random number generator used to search for magic numbers.
+const MAGIC_SEED: u64 = 0x5eed_0f5a_4a1c_b0a2;
+
+/// The squares attacked by a knight on the given square.
+pub fn knight_attacks(square: Square) -> u64 {
+    tables().knight[index(square)]
+}
+
+/// The squares attacked by a king on the given square.
+pub fn king_attacks(square: Square) -> u64 {
+    tables().king[index(square)]
+}
+
+/// The squares attacked by a pawn of the given color on the given square. These are the squares it could capture on.
+pub fn pawn_attacks(color: PieceColor, square: Square) -> u64 {
+    match color {
+        PieceColor::White => tables().white_pawn[index(square)],
+        PieceColor::Black => tables().black_pawn[index(square)],
+    }
+}
+
+/// The squares attacked by a rook on the given square, with the pieces in `occupied` blocking its path.
+/// The attacked squares include the first blocker in each direction, regardless of its color.
+pub fn rook_attacks(square: Square, occupied: u64) -> u64 {
+    tables().rook.attacks(index(square), occupied)
+}
+
+/// The squares attacked by a bishop on the given square, with the pieces in `occupied` blocking its path.
+/// The attacked squares include the first blocker in each direction, regardless of its color.
+pub fn bishop_attacks(square: Square, occupied: u64) -> u64 {
+    tables().bishop.attacks(index(square), occupied)
+}
+
+/// The squares attacked by a queen on the given square, with the pieces in `occupied` blocking its path.
+pub fn queen_attacks(square: Square, occupied: u64) -> u64 {
+    rook_attacks(square, occupied) | bishop_attacks(square, occupied)
+}
+
+/// Computes rook attacks by walking each ray square by square. This is much slower than `rook_attacks`, and mostly
+/// useful as a reference implementation.
+pub fn rook_attacks_naive(square: Square, occupied: u64) -> u64 {
+    ray_attacks(square, occupied, &ROOK_DIRECTIONS)
+}
+
+/// Computes bishop attacks by walking each ray square by square, see `rook_attacks_naive`.
+pub fn bishop_attacks_naive(square: Square, occupied: u64) -> u64 {
+    ray_attacks(square, occupied, &BISHOP_DIRECTIONS)
+}
+
+/// Forces the attack tables to be built now, instead of the first time they are used.
+/// Searching for the magic numbers takes a noticeable fraction of a second, which may be unwanted in the middle of a
+/// search.
+pub fn init() {
+    tables();
+}
+
+// The bit index of a square. Panics if the square is not on an 8x8 board.
+fn index(square: Square) -> usize {
+    assert!(square.row < 8 && square.column < 8, "Square {square} is not on an 8x8 board");
+    square.row as usize * 8 + square.column as usize
+}
+
+fn bit(square: Square) -> u64 {
+    1 << index(square)
+}
+
+fn on_board(square: Square) -> bool {
+    square.row < 8 && square.column < 8
+}
+
+fn all_squares() -> impl Iterator<Item = Square> {
+    (0..8).flat_map(|row| (0..8).map(move |column| Square::new(row, column)))
+}
+
+fn ray_attacks(square: Square, occupied: u64, directions: &[(i32, i32)]) -> u64 {
+    let mut attacks = 0;
+    for &(rows, columns) in directions {
+        let mut current = square;
+        while let Some(next) = current.offset(rows, columns).filter(|&next| on_board(next)) {
+            attacks |= bit(next);
+            if occupied & bit(next) != 0 {
+                break;
+            }
+            current = next;
+        }
+    }
+    attacks
+}
+
+// The squares a piece jumps to, for pieces that do not slide.
+fn jump_attacks(square: Square, offsets: &[(i32, i32)]) -> u64 {
+    offsets.iter()
+        .filter_map(|&(rows, columns)| square.offset(rows, columns))
+        .filter(|&target| on_board(target))
+        .fold(0, |attacks, target| attacks | bit(target))
+}
+
+// The squares whose occupancy matters for a slider on the given square. The last square of each ray is left out,
+// since the attacks include it whether it is occupied or not.
+fn relevant_blockers(square: Square, directions: &[(i32, i32)]) -> u64 {
+    let mut mask = 0;
+    for &(rows, columns) in directions {
+        let mut current = square;
+        while let Some(next) = current.offset(rows, columns).filter(|&next| on_board(next)) {
+            if next.offset(rows, columns).is_none_or(|after| !on_board(after)) {
+                break;
+            }
+            mask |= bit(next);
+            current = next;
+        }
+    }
+    mask
+}
+
+struct Tables {
+    knight: [u64; 64],
+    king: [u64; 64],
+    white_pawn: [u64; 64],
+    black_pawn: [u64; 64],
+    rook: SliderTable,
+    bishop: SliderTable,
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let mut rng = Rng(MAGIC_SEED);
+        let mut tables = Tables {
+            knight: [0; 64],
+            king: [0; 64],
+            white_pawn: [0; 64],
+            black_pawn: [0; 64],
+            rook: SliderTable::new(&ROOK_DIRECTIONS, &mut rng),
+            bishop: SliderTable::new(&BISHOP_DIRECTIONS, &mut rng),
+        };
+        for square in all_squares() {
+            let index = index(square);
+            tables.knight[index] = jump_attacks(square, &KNIGHT_OFFSETS);
+            tables.king[index] = jump_attacks(square, &KING_OFFSETS);
+            tables.white_pawn[index] = jump_attacks(square, &[(1, -1), (1, 1)]);
+            tables.black_pawn[index] = jump_attacks(square, &[(-1, -1), (-1, 1)]);
+        }
+        tables
+    })
+}
+
+// Everything needed to look up the attacks of a slider on a single square.
+#[derive(Debug, Copy, Clone, Default)]
+struct Magic {
+    mask: u64,
+    magic: u64,
+    shift: u32,
+    offset: usize,
+}
+
+impl Magic {
+    fn key(&self, occupied: u64) -> usize {
+        ((occupied & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+// The magics for one type of slider, sharing a single table of attacks for all squares.
+struct SliderTable {
+    magics: [Magic; 64],
+    attacks: Vec<u64>,
+}
+
+impl SliderTable {
+    fn new(directions: &[(i32, i32)], rng: &mut Rng) -> Self {
+        let mut magics = [Magic::default(); 64];
+        let mut attacks = Vec::new();
+
+        for square in all_squares() {
+            let mask = relevant_blockers(square, directions);
+            let bits = mask.count_ones();
+
+            // Every subset of the relevant blockers, together with the attacks it results in.
+            let subsets: Vec<(u64, u64)> = subsets(mask)
+                .map(|blockers| (blockers, ray_attacks(square, blockers, directions)))
+                .collect();
+
+            let mut magic = Magic { mask, magic: 0, shift: 64 - bits, offset: attacks.len() };
+            let mut table = vec![0; 1 << bits];
+            // The attempt each entry was last written in, so the table does not have to be cleared between attempts.
+            let mut written = vec![0; 1 << bits];
+            for attempt in 1.. {
+                // Magic numbers with few bits set tend to work best.
+                magic.magic = rng.next() & rng.next() & rng.next();
+                if (mask.wrapping_mul(magic.magic) >> 56).count_ones() < 6 {
+                    continue;
+                }
+                if fill(&magic, &subsets, &mut table, &mut written, attempt) {
+                    break;
+                }
+            }
+
+            magics[index(square)] = magic;
+            attacks.extend(table);
+        }
+
+        Self { magics, attacks }
+    }
+
+    fn attacks(&self, index: usize, occupied: u64) -> u64 {
+        let magic = &self.magics[index];
+        self.attacks[magic.offset + magic.key(occupied)]
+    }
+}
+
+// Tries to fill the table using a magic number. Fails if two blocker sets with different attacks share an entry.
+// Different blocker sets with the same attacks may share an entry, which is what makes the tables small.
+fn fill(magic: &Magic, subsets: &[(u64, u64)], table: &mut [u64], written: &mut [u32], attempt: u32) -> bool {
+    for &(blockers, attacks) in subsets {
+        let key = magic.key(blockers);
+        if written[key] == attempt && table[key] != attacks {
+            return false;
+        }
+        written[key] = attempt;
+        table[key] = attacks;
+    }
+    true
+}
+
+// Iterates over all subsets of a set of squares, including the empty set and the set itself.
+fn subsets(mask: u64) -> impl Iterator<Item = u64> {
+    let mut subset = 0u64;
+    let mut done = false;
+    std::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+        let current = subset;
+        // The Carry-Rippler trick: steps to the next subset of the mask, wrapping back to 0 after the full mask.
+        subset = subset.wrapping_sub(mask) & mask;
+        done = subset == 0;
+        Some(current)
+    })
+}
+
+// A xorshift64* generator. Only used to find magic numbers, so it does not need to be cryptographically secure.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::test_rng::TestRng;
+    use super::*;
+
+    fn squares(names: &[&str]) -> u64 {
+        names.iter().fold(0, |set, name| set | bit(Square::parse(name).unwrap()))
+    }
+
+    #[test]
+    fn jumping_pieces() {
+        let a1 = Square::parse("a1").unwrap();
+        let e4 = Square::parse("e4").unwrap();
+        assert_eq!(knight_attacks(a1), squares(&["b3", "c2"]));
+        assert_eq!(knight_attacks(e4).count_ones(), 8);
+        assert_eq!(king_attacks(a1), squares(&["a2", "b1", "b2"]));
+        assert_eq!(king_attacks(e4).count_ones(), 8);
+        assert_eq!(pawn_attacks(PieceColor::White, e4), squares(&["d5", "f5"]));
+        assert_eq!(pawn_attacks(PieceColor::Black, e4), squares(&["d3", "f3"]));
+        assert_eq!(pawn_attacks(PieceColor::White, Square::parse("h2").unwrap()), squares(&["g3"]));
+        assert_eq!(pawn_attacks(PieceColor::White, Square::parse("h8").unwrap()), 0);
+    }
+
+    #[test]
+    fn sliders() {
+        let d4 = Square::parse("d4").unwrap();
+        let occupied = squares(&["d6", "b4", "f6", "a1", "d2"]);
+        assert_eq!(rook_attacks(d4, occupied), squares(&["d5", "d6", "d3", "d2", "c4", "b4", "e4", "f4", "g4", "h4"]));
+        assert_eq!(bishop_attacks(d4, occupied), squares(&["e5", "f6", "c5", "b6", "a7", "c3", "b2", "a1", "e3", "f2", "g1"]));
+        assert_eq!(queen_attacks(d4, occupied), rook_attacks(d4, occupied) | bishop_attacks(d4, occupied));
+        assert_eq!(rook_attacks(Square::parse("a1").unwrap(), 0).count_ones(), 14);
+    }
+
+    #[test]
+    fn blocker_masks() {
+        assert_eq!(relevant_blockers(Square::parse("a1").unwrap(), &ROOK_DIRECTIONS).count_ones(), 12);
+        assert_eq!(relevant_blockers(Square::parse("e4").unwrap(), &ROOK_DIRECTIONS).count_ones(), 10);
+        assert_eq!(relevant_blockers(Square::parse("e4").unwrap(), &BISHOP_DIRECTIONS).count_ones(), 9);
+        assert_eq!(subsets(squares(&["a1", "b2", "c3"])).count(), 8);
+        assert_eq!(subsets(0).collect::<Vec<_>>(), [0]);
+    }
+
+    #[test]
+    fn magic_matches_naive() {
+        let mut rng = TestRng::new(0x2545_f491_4f6c_dd1d);
+        for square in all_squares() {
+            for _ in 0..200 {
+                // Sparse and dense occupancies
+                let occupied = match rng.next(3) {
+                    0 => rng.next_u64() & rng.next_u64(),
+                    1 => rng.next_u64(),
+                    _ => rng.next_u64() | rng.next_u64(),
+                };
+                assert_eq!(rook_attacks(square, occupied), rook_attacks_naive(square, occupied), "rook on {square}");
+                assert_eq!(bishop_attacks(square, occupied), bishop_attacks_naive(square, occupied), "bishop on {square}");
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn off_board_square() {
+        rook_attacks(Square::new(0, 8), 0);
+    }
+}
@@ -0,0 +1,316 @@
+use anyhow::{ensure, Context};
+use crate::board::Board;
+use crate::square::Square;
+use crate::standard::piece::{ColoredStandardPiece, PieceColor, StandardPiece};
+
+/// The width and height of a bitboard. Every square corresponds to one bit of a `u64`.
+pub const BITBOARD_SIZE: u16 = 8;
+
+// Every token that can be on a bitboard, so `get` can hand out references even though only bits are stored.
+// Indexed by the color index, then the piece index.
+pub(crate) static TOKENS: [[ColoredStandardPiece; 6]; 2] = [
+    tokens(PieceColor::White),
+    tokens(PieceColor::Black),
+];
+
+const fn tokens(color: PieceColor) -> [ColoredStandardPiece; 6] {
+    [
+        ColoredStandardPiece::new(StandardPiece::Pawn, color),
+        ColoredStandardPiece::new(StandardPiece::Knight, color),
+        ColoredStandardPiece::new(StandardPiece::Bishop, color),
+        ColoredStandardPiece::new(StandardPiece::Rook, color),
+        ColoredStandardPiece::new(StandardPiece::Queen, color),
+        ColoredStandardPiece::new(StandardPiece::King, color),
+    ]
+}
+
+/// A regular 8x8 board storing standard chess pieces as bitboards: one set of squares per piece type and one per color.
+/// Square a1 is bit 0, b1 is bit 1, and so on up to h8, which is bit 63.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct BitboardBoard {
+    pieces: [u64; 6],
+    colors: [u64; 2],
+}
+
+impl BitboardBoard {
+    /// Creates a new empty board.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copies the pieces of any other 8x8 board.
+    ///
+    /// Returns Err(_) if the other board does not have the same size.
+    pub fn from_board<B: Board<Token = ColoredStandardPiece>>(board: &B) -> anyhow::Result<Self> {
+        ensure!(board.width() == BITBOARD_SIZE && board.height() == BITBOARD_SIZE,
+            "Board with size ({}, {}) does not fit in a bitboard", board.width(), board.height());
+
+        let mut bitboard = Self::new();
+        for (square, token) in board.iter() {
+            bitboard.set(square, Some(token.clone()))?;
+        }
+        Ok(bitboard)
+    }
+
+    /// The set of all occupied squares.
+    pub fn occupancy(&self) -> u64 {
+        self.colors[0] | self.colors[1]
+    }
+
+    /// The set of squares occupied by pieces of the given color.
+    pub fn color(&self, color: PieceColor) -> u64 {
+        self.colors[color_index(color)]
+    }
+
+    /// The set of squares occupied by the given piece type, of either color.
+    pub fn piece(&self, piece: StandardPiece) -> u64 {
+        self.pieces[piece_index(piece)]
+    }
+
+    /// The set of squares occupied by the given piece type of the given color.
+    pub fn pieces(&self, piece: StandardPiece, color: PieceColor) -> u64 {
+        self.piece(piece) & self.color(color)
+    }
+
+    /// The number of pieces of the given type and color on the board.
+    pub fn count(&self, piece: StandardPiece, color: PieceColor) -> u32 {
+        self.pieces(piece, color).count_ones()
+    }
+
+    fn remove(&mut self, bit: u64) {
+        for set in self.pieces.iter_mut().chain(self.colors.iter_mut()) {
+            *set &= !bit;
+        }
+    }
+}
+
+impl Board for BitboardBoard {
+    type Token = ColoredStandardPiece;
+
+    fn width(&self) -> u16 {
+        BITBOARD_SIZE
+    }
+
+    fn height(&self) -> u16 {
+        BITBOARD_SIZE
+    }
+
+    fn valid_square(&self, square: Square) -> bool {
+        square.row < BITBOARD_SIZE && square.column < BITBOARD_SIZE
+    }
+
+    fn get(&self, square: Square) -> Option<&Self::Token> {
+        let bit = square_bit(square)?;
+        let color = self.colors.iter().position(|&set| set & bit != 0)?;
+        let piece = self.pieces.iter().position(|&set| set & bit != 0)?;
+        Some(&TOKENS[color][piece])
+    }
+
+    fn set(&mut self, square: Square, token: Option<Self::Token>) -> anyhow::Result<()> {
+        let bit = square_bit(square).with_context(|| {
+            format!("Board index ({}, {}) is out of bounds for board with size ({}, {})", square.row, square.column, BITBOARD_SIZE, BITBOARD_SIZE)
+        })?;
+
+        self.remove(bit);
+        if let Some(token) = token {
+            self.pieces[piece_index(token.piece())] |= bit;
+            self.colors[color_index(token.color())] |= bit;
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    fn make_move(&mut self, from: Square, to: Square) -> anyhow::Result<Option<Self::Token>> {
+        ensure!(self.valid_square(from), "Source square is not inside the board");
+        ensure!(self.valid_square(to), "Destination square is not inside the board");
+
+        let Some(token) = self.at(from) else { return Ok(None); };
+        if from == to {
+            return Ok(None);
+        }
+        let captured = self.at(to);
+        self.set(from, None)?;
+        self.set(to, Some(token))?;
+        Ok(captured)
+    }
+
+    fn occupied(&self) -> impl Iterator<Item = Square> {
+        squares_in(self.occupancy())
+    }
+}
+
+/// The bit belonging to a square, or `None` if the square is not on an 8x8 board.
+pub fn square_bit(square: Square) -> Option<u64> {
+    if square.row >= BITBOARD_SIZE || square.column >= BITBOARD_SIZE {
+        return None;
+    }
+    Some(1 << (square.row * BITBOARD_SIZE + square.column))
+}
+
+/// The square belonging to a bit index between 0 and 63.
+pub fn index_square(index: u32) -> Square {
+    Square::new((index / BITBOARD_SIZE as u32) as u16, (index % BITBOARD_SIZE as u32) as u16)
+}
+
+/// Iterates over the squares in a set, from a1 to h8.
+pub fn squares_in(set: u64) -> SquareIter {
+    SquareIter(set)
+}
+
+/// An iterator over the squares in a bitboard, see `squares_in`.
+#[derive(Debug, Copy, Clone)]
+pub struct SquareIter(u64);
+
+impl Iterator for SquareIter {
+    type Item = Square;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0 == 0 {
+            return None;
+        }
+        let index = self.0.trailing_zeros();
+        // Clear the lowest set bit
+        self.0 &= self.0 - 1;
+        Some(index_square(index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let count = self.0.count_ones() as usize;
+        (count, Some(count))
+    }
+}
+
+impl ExactSizeIterator for SquareIter {}
+
+pub(crate) fn piece_index(piece: StandardPiece) -> usize {
+    match piece {
+        StandardPiece::Pawn => 0,
+        StandardPiece::Knight => 1,
+        StandardPiece::Bishop => 2,
+        StandardPiece::Rook => 3,
+        StandardPiece::Queen => 4,
+        StandardPiece::King => 5,
+    }
+}
+
+pub(crate) fn color_index(color: PieceColor) -> usize {
+    match color {
+        PieceColor::White => 0,
+        PieceColor::Black => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::mailbox::MailboxBoard;
+    use crate::board::test_rng::TestRng;
+    use crate::standard::perft::perft;
+    use crate::standard::position::StandardPosition;
+    use super::*;
+
+    // Usually a square on the board, sometimes one just outside of it.
+    fn random_square(rng: &mut TestRng) -> Square {
+        Square::new(rng.next(10), rng.next(10))
+    }
+
+    fn assert_same(bitboard: &BitboardBoard, mailbox: &MailboxBoard<ColoredStandardPiece>) {
+        for row in 0..10 {
+            for column in 0..10 {
+                let square = Square::new(row, column);
+                assert_eq!(bitboard.valid_square(square), mailbox.valid_square(square), "{square}");
+                assert_eq!(bitboard.at(square), mailbox.at(square), "{square}");
+            }
+        }
+    }
+
+    #[test]
+    fn set_and_get() {
+        let mut board = BitboardBoard::new();
+        let knight = ColoredStandardPiece::new(StandardPiece::Knight, PieceColor::Black);
+        board.set(Square::parse("g8").unwrap(), Some(knight.clone())).unwrap();
+        assert_eq!(board.at(Square::parse("g8").unwrap()), Some(knight));
+        assert_eq!(board.occupancy(), 1 << 62);
+        assert_eq!(board.count(StandardPiece::Knight, PieceColor::Black), 1);
+        assert_eq!(board.count(StandardPiece::Knight, PieceColor::White), 0);
+
+        // Replacing a piece removes it from its old sets
+        let queen = ColoredStandardPiece::new(StandardPiece::Queen, PieceColor::White);
+        board.set(Square::parse("g8").unwrap(), Some(queen.clone())).unwrap();
+        assert_eq!(board.at(Square::parse("g8").unwrap()), Some(queen));
+        assert_eq!(board.piece(StandardPiece::Knight), 0);
+        assert_eq!(board.color(PieceColor::Black), 0);
+
+        assert!(board.set(Square::new(0, 8), None).is_err());
+        assert!(board.at(Square::new(8, 0)).is_none());
+    }
+
+    #[test]
+    fn square_iteration() {
+        let squares: Vec<String> = squares_in(0x8000_0000_0000_0081).map(|square| square.to_string()).collect();
+        assert_eq!(squares, ["a1", "h1", "h8"]);
+        assert_eq!(squares_in(0).len(), 0);
+
+        let position = StandardPosition::starting_position();
+        let board = BitboardBoard::from_board(position.board()).unwrap();
+        assert_eq!(squares_in(board.pieces(StandardPiece::Rook, PieceColor::White)).collect::<Vec<_>>(),
+            [Square::parse("a1").unwrap(), Square::parse("h1").unwrap()]);
+        assert_eq!(board.occupancy().count_ones(), 32);
+        assert_eq!(board.occupied().count(), 32);
+        assert_eq!(board.count(StandardPiece::Pawn, PieceColor::Black), 8);
+    }
+
+    #[test]
+    fn from_board_requires_8x8() {
+        assert!(BitboardBoard::from_board(&MailboxBoard::<ColoredStandardPiece>::new(10, 8)).is_err());
+    }
+
+    #[test]
+    fn equivalent_to_mailbox() {
+        let mut rng = TestRng::new(0x9e37_79b9_7f4a_7c15);
+        let mut bitboard = BitboardBoard::new();
+        let mut mailbox = MailboxBoard::new(8, 8);
+
+        for step in 0..5000 {
+            match rng.next(10) {
+                0..=4 => {
+                    let (square, token) = (random_square(&mut rng), rng.token());
+                    assert_eq!(bitboard.set(square, token.clone()).is_ok(), mailbox.set(square, token).is_ok(), "step {step}");
+                }
+                5..=8 => {
+                    let (from, to) = (random_square(&mut rng), random_square(&mut rng));
+                    let expected = mailbox.make_move(from, to).ok();
+                    assert_eq!(bitboard.make_move(from, to).ok(), expected, "step {step}: {from} to {to}");
+                }
+                _ => {
+                    // Clear only occasionally, so the boards fill up in between.
+                    if rng.next(20) == 0 {
+                        bitboard.clear();
+                        mailbox.clear();
+                    }
+                }
+            }
+            assert_same(&bitboard, &mailbox);
+        }
+    }
+
+    #[test]
+    fn same_perft_as_mailbox() {
+        for fen in [
+            crate::standard::fen::STARTING_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        ] {
+            let mailbox = StandardPosition::from_fen(fen).unwrap();
+            let mut bitboard = StandardPosition::new(BitboardBoard::from_board(mailbox.board()).unwrap());
+            bitboard.set_side_to_move(mailbox.side_to_move());
+            bitboard.set_castling_rights(mailbox.castling_rights());
+            bitboard.set_en_passant(mailbox.en_passant());
+
+            assert_eq!(perft(&bitboard, 2), perft(&mailbox, 2), "{fen}");
+            assert_eq!(bitboard.to_fen(), mailbox.to_fen());
+        }
+    }
+}
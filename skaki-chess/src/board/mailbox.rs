@@ -1,8 +1,9 @@
-use anyhow::{bail, ensure, Context};
+use anyhow::{bail, Context};
 use crate::board::Board;
 use crate::square::Square;
 
 /// A simple array-based board, storing tokens in a list with each element corresponding to a token (or no token).
+#[derive(Debug, Clone)]
 pub struct MailboxBoard<T> {
     width: u16,
     height: u16,
@@ -10,8 +11,12 @@ pub struct MailboxBoard<T> {
 }
 
 impl<T: Clone> MailboxBoard<T> {
-    fn index(&self, row: u16, column: u16) -> usize {
-        (row * self.width + column) as usize
+    // The index of a square in the token list, or `None` if the square is outside the board.
+    fn index(&self, square: Square) -> Option<usize> {
+        if square.row >= self.height || square.column >= self.width {
+            return None;
+        }
+        Some(square.row as usize * self.width as usize + square.column as usize)
     }
 
     /// Creates a new empty board.
@@ -35,20 +40,18 @@ impl<T: Clone> Board for MailboxBoard<T> {
     }
 
     fn valid_square(&self, square: Square) -> bool {
-        let index = self.index(square.row, square.column);
-        index < self.tokens.len()
+        self.index(square).is_some()
     }
 
-    fn at(&self, square: Square) -> Option<Self::Token> {
-        let index = self.index(square.row, square.column);
-        self.tokens.get(index)?.clone()
+    fn get(&self, square: Square) -> Option<&Self::Token> {
+        let index = self.index(square)?;
+        self.tokens[index].as_ref()
     }
 
     fn set(&mut self, square: Square, token: Option<Self::Token>) -> anyhow::Result<()> {
-        let index = self.index(square.row, square.column);
-        if index >= self.tokens.len() {
+        let Some(index) = self.index(square) else {
             bail!("Board index ({}, {}) is out of bounds for board with size ({}, {})", square.row, square.column, self.width(), self.height());
-        }
+        };
         self.tokens[index] = token;
         Ok(())
     }
@@ -57,15 +60,24 @@ impl<T: Clone> Board for MailboxBoard<T> {
         self.tokens.fill(None);
     }
 
-    fn make_move(&mut self, from: Square, to: Square) -> anyhow::Result<()> {
-        ensure!(self.valid_square(from), "Source square is not inside the board");
+    fn make_move(&mut self, from: Square, to: Square) -> anyhow::Result<Option<Self::Token>> {
+        let from = self.index(from).context("Source square is not inside the board")?;
+        let to = self.index(to).context("Destination square is not inside the board")?;
 
-        if let Some(token) = self.at(from) {
-            self.set(to, Some(token))
-                .with_context(|| "Destination square is not inside the board".to_string())?;
+        if from == to || self.tokens[from].is_none() {
+            return Ok(None);
         }
+        let token = self.tokens[from].take();
+        Ok(std::mem::replace(&mut self.tokens[to], token))
+    }
 
-        Ok(())
+    fn iter(&self) -> impl Iterator<Item = (Square, &Self::Token)> {
+        // Walking the token list directly is a lot faster than looking up every square.
+        let width = self.width as usize;
+        self.tokens.iter().enumerate().filter_map(move |(index, token)| {
+            let square = Square::new((index / width) as u16, (index % width) as u16);
+            token.as_ref().map(|token| (square, token))
+        })
     }
 }
 
@@ -73,6 +85,7 @@ impl<T: Clone> Board for MailboxBoard<T> {
 mod tests {
     use crate::board::Board;
     use crate::board::mailbox::MailboxBoard;
+    use crate::board::test_rng::TestRng;
     use crate::square::Square;
 
     // Anything can be a piece.
@@ -99,6 +112,17 @@ mod tests {
         assert_eq!(board.at(Square::new(2, 1)), None);
     }
 
+    #[test]
+    fn usable_as_trait_object() {
+        let mut boards: Vec<Box<dyn Board<Token = Piece>>> = vec![Box::new(MailboxBoard::new(4, 4)), Box::new(MailboxBoard::new(8, 2))];
+        for board in &mut boards {
+            board.set(Square::new(1, 3), Some(Piece::Bob)).unwrap();
+            assert_eq!(board.make_move(Square::new(1, 3), Square::new(0, 0)).unwrap(), None);
+            assert_eq!(board.at(Square::new(0, 0)), Some(Piece::Bob));
+        }
+        assert_eq!(boards[1].width(), 8);
+    }
+
     #[test]
     fn out_of_bounds() {
         let mut board = MailboxBoard::<Piece>::new(4, 4);
@@ -107,6 +131,117 @@ mod tests {
         assert!(board.set(Square::new(10, 10), Some(Piece::Alice)).is_err());
     }
 
+    #[test]
+    fn columns_do_not_wrap_around() {
+        let mut board = MailboxBoard::<Piece>::new(8, 8);
+        assert!(!board.valid_square(Square::new(0, 8)));
+        assert!(!board.valid_square(Square::new(0, 10)));
+        assert!(board.set(Square::new(0, 10), Some(Piece::Bob)).is_err());
+        assert_eq!(board.at(Square::new(1, 2)), None);
+        assert_eq!(board.at(Square::new(0, 10)), None);
+    }
+
+    #[test]
+    fn large_board() {
+        let mut board = MailboxBoard::<Piece>::new(1000, 1000);
+        let corner = Square::new(999, 999);
+        board.set(corner, Some(Piece::Alice)).expect("Corner is on the board");
+        assert_eq!(board.at(corner), Some(Piece::Alice));
+        assert!(!board.valid_square(Square::new(1000, 0)));
+    }
+
+    #[test]
+    fn no_aliasing_on_random_sizes() {
+        let mut rng = TestRng::new(0x2545_f491_4f6c_dd1d);
+        for _ in 0..200 {
+            let width = rng.next(20) + 1;
+            let height = rng.next(20) + 1;
+            let mut board = MailboxBoard::<(u16, u16)>::new(width, height);
+
+            // Every square on the board stores its own coordinates.
+            for row in 0..height {
+                for column in 0..width {
+                    board.set(Square::new(row, column), Some((row, column))).expect("Square is on the board");
+                }
+            }
+
+            // Squares outside the board, including ones just past an edge, must never be accepted.
+            for _ in 0..50 {
+                let square = Square::new(rng.next(height + 5), rng.next(width + 5));
+                let inside = square.row < height && square.column < width;
+                assert_eq!(board.valid_square(square), inside, "{square:?} on {width}x{height}");
+                if inside {
+                    assert_eq!(board.at(square), Some((square.row, square.column)));
+                } else {
+                    assert_eq!(board.at(square), None);
+                    assert!(board.set(square, Some((0, 0))).is_err());
+                }
+            }
+
+            // Failed writes must not have changed anything.
+            for row in 0..height {
+                for column in 0..width {
+                    assert_eq!(board.at(Square::new(row, column)), Some((row, column)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn make_move() {
+        let mut board = MailboxBoard::new(4, 4);
+        board.set(Square::new(0, 0), Some(Piece::Bob)).unwrap();
+        board.set(Square::new(2, 2), Some(Piece::Alice)).unwrap();
+
+        // Moving to an empty square vacates the source
+        assert_eq!(board.make_move(Square::new(0, 0), Square::new(1, 1)).unwrap(), None);
+        assert_eq!(board.at(Square::new(0, 0)), None);
+        assert_eq!(board.at(Square::new(1, 1)), Some(Piece::Bob));
+
+        // Moving onto a token returns it
+        assert_eq!(board.make_move(Square::new(1, 1), Square::new(2, 2)).unwrap(), Some(Piece::Alice));
+        assert_eq!(board.at(Square::new(1, 1)), None);
+        assert_eq!(board.at(Square::new(2, 2)), Some(Piece::Bob));
+
+        // Moving from an empty square or onto the same square does nothing
+        assert_eq!(board.make_move(Square::new(0, 0), Square::new(2, 2)).unwrap(), None);
+        assert_eq!(board.make_move(Square::new(2, 2), Square::new(2, 2)).unwrap(), None);
+        assert_eq!(board.at(Square::new(2, 2)), Some(Piece::Bob));
+    }
+
+    #[test]
+    fn make_move_off_board() {
+        let mut board = MailboxBoard::new(4, 4);
+        board.set(Square::new(0, 0), Some(Piece::Bob)).unwrap();
+
+        assert!(board.make_move(Square::new(0, 0), Square::new(0, 4)).is_err());
+        assert!(board.make_move(Square::new(4, 0), Square::new(0, 0)).is_err());
+        // Nothing should have changed
+        assert_eq!(board.at(Square::new(0, 0)), Some(Piece::Bob));
+        assert_eq!(board.at(Square::new(1, 0)), None);
+    }
+
+    #[test]
+    fn iteration() {
+        let mut board = MailboxBoard::new(3, 2);
+        board.set(Square::new(1, 0), Some(Piece::Bob)).unwrap();
+        board.set(Square::new(0, 2), Some(Piece::Alice)).unwrap();
+
+        let squares: Vec<Square> = board.squares().collect();
+        assert_eq!(squares.len(), 6);
+        assert_eq!(squares[0], Square::new(0, 0));
+        assert_eq!(squares[3], Square::new(1, 0));
+
+        assert_eq!(board.occupied().collect::<Vec<_>>(), [Square::new(0, 2), Square::new(1, 0)]);
+        assert_eq!(board.iter().collect::<Vec<_>>(), [(Square::new(0, 2), &Piece::Alice), (Square::new(1, 0), &Piece::Bob)]);
+        assert_eq!(board.find(|token| *token == Piece::Bob), Some(Square::new(1, 0)));
+        assert_eq!(MailboxBoard::<Piece>::new(3, 2).find(|_| true), None);
+
+        assert_eq!(board.get(Square::new(1, 0)), Some(&Piece::Bob));
+        assert_eq!(board.get(Square::new(1, 1)), None);
+        assert_eq!(board.get(Square::new(1, 3)), None);
+    }
+
     #[test]
     fn clear_board() {
         let mut board = MailboxBoard::new(4, 4);
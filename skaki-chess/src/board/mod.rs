@@ -1,6 +1,11 @@
 use crate::square::Square;
 
+pub mod attacks;
+pub mod bitboard;
 pub mod mailbox;
+pub mod wide;
+#[cfg(test)]
+mod test_rng;
 
 /// A generic representation of a chess board.
 /// A board is any rectangular arrangement of squares, with tokens places on certain squares, and others being empty.
@@ -18,10 +23,17 @@ pub trait Board {
     /// Whether this square is a valid square on the board.
     fn valid_square(&self, square: Square) -> bool;
 
-    /// Get the token at the specified location.
+    /// Borrow the token at the specified location.
     ///
     /// Always returns `None` if the square is outside the board.
-    fn at(&self, square: Square) -> Option<Self::Token>;
+    fn get(&self, square: Square) -> Option<&Self::Token>;
+
+    /// Get a copy of the token at the specified location.
+    ///
+    /// Always returns `None` if the square is outside the board.
+    fn at(&self, square: Square) -> Option<Self::Token> where Self::Token: Clone {
+        self.get(square).cloned()
+    }
 
     /// Set the token at the specified location. Set to `None` to clear the square instead.
     ///
@@ -31,8 +43,33 @@ pub trait Board {
     /// Clears the entire board, setting each square to the empty token.
     fn clear(&mut self);
 
-    /// Executes a move on the board. If there is a token on the starting square, set the token of the destination square
-    /// to this token. Otherwise, do nothing.
+    /// Executes a move on the board. If there is a token on the starting square, it is moved to the destination square,
+    /// leaving the starting square empty. Otherwise, do nothing.
     /// Does not check the legality of moves in any way.
-    fn make_move(&mut self, from: Square, to: Square) -> anyhow::Result<()>;
+    ///
+    /// Returns the token that was on the destination square before the move, if any.
+    /// Returns Err(_) if either square is outside the board, in which case the board is left unchanged.
+    fn make_move(&mut self, from: Square, to: Square) -> anyhow::Result<Option<Self::Token>>;
+
+    /// Iterates over every square on the board, row by row starting at the first row, and each row from the first
+    /// column to the last.
+    fn squares(&self) -> impl Iterator<Item = Square> where Self: Sized {
+        let width = self.width();
+        (0..self.height()).flat_map(move |row| (0..width).map(move |column| Square::new(row, column)))
+    }
+
+    /// Iterates over every square with a token on it, in the same order as `squares`.
+    fn occupied(&self) -> impl Iterator<Item = Square> where Self: Sized {
+        self.iter().map(|(square, _)| square)
+    }
+
+    /// Iterates over every token on the board together with its square, in the same order as `squares`.
+    fn iter(&self) -> impl Iterator<Item = (Square, &Self::Token)> where Self: Sized {
+        self.squares().filter_map(|square| self.get(square).map(|token| (square, token)))
+    }
+
+    /// The first square, in the same order as `squares`, with a token that matches the predicate.
+    fn find(&self, mut predicate: impl FnMut(&Self::Token) -> bool) -> Option<Square> where Self: Sized {
+        self.iter().find(|(_, token)| predicate(token)).map(|(square, _)| square)
+    }
 }
@@ -0,0 +1,37 @@
+/// A tiny xorshift generator, so the randomized board tests are deterministic without external crates.
+/// The attack benchmark includes this file as well, which is why it does not depend on anything else in the crate.
+pub struct TestRng(u64);
+
+impl TestRng {
+    /// Starts the generator from the given seed, which must not be zero.
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// The next 64 random bits.
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A random number below `bound`.
+    pub fn next(&mut self, bound: u16) -> u16 {
+        (self.next_u64() % bound as u64) as u16
+    }
+}
+
+#[cfg(test)]
+impl TestRng {
+    /// A random standard piece, or no piece at all one in thirteen times.
+    pub fn token(&mut self) -> Option<crate::standard::piece::ColoredStandardPiece> {
+        use crate::standard::piece::{ColoredStandardPiece, PieceColor, StandardPiece};
+
+        let pieces = [StandardPiece::Pawn, StandardPiece::Knight, StandardPiece::Bishop, StandardPiece::Rook,
+            StandardPiece::Queen, StandardPiece::King];
+        let index = self.next(13) as usize;
+        let color = if index < 6 { PieceColor::White } else { PieceColor::Black };
+        (index < 12).then(|| ColoredStandardPiece::new(pieces[index % 6], color))
+    }
+}
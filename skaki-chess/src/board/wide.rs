@@ -0,0 +1,453 @@
+use std::ops::{BitAnd, BitOr, BitXor, Not, Shl, Shr};
+use anyhow::{ensure, Context};
+use crate::board::Board;
+use crate::board::bitboard::{color_index, piece_index, TOKENS};
+use crate::square::Square;
+use crate::standard::piece::{ColoredStandardPiece, PieceColor, StandardPiece};
+
+/// A set of squares stored in `LIMBS` 64-bit words, for boards with more than 64 squares.
+/// Square `(row, column)` is bit `row * width + column`, counting from the lowest bit of the first limb.
+/// The set itself does not know the size of the board, see `WideBitboardBoard` for operations that need it.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct WideBitboard<const LIMBS: usize> {
+    limbs: [u64; LIMBS],
+}
+
+impl<const LIMBS: usize> WideBitboard<LIMBS> {
+    /// The number of bits in the set.
+    pub const BITS: u32 = LIMBS as u32 * 64;
+
+    /// The empty set.
+    pub fn empty() -> Self {
+        Self { limbs: [0; LIMBS] }
+    }
+
+    /// The set containing only the given bit.
+    pub fn bit(index: u32) -> Self {
+        let mut set = Self::empty();
+        set.set(index);
+        set
+    }
+
+    /// The set of the lowest `count` bits.
+    pub fn lowest(count: u32) -> Self {
+        let mut set = Self::empty();
+        for (i, limb) in set.limbs.iter_mut().enumerate() {
+            let start = i as u32 * 64;
+            *limb = match count.saturating_sub(start) {
+                0 => 0,
+                bits if bits >= 64 => u64::MAX,
+                bits => (1 << bits) - 1,
+            };
+        }
+        set
+    }
+
+    /// Whether the given bit is in the set.
+    pub fn contains(&self, index: u32) -> bool {
+        index < Self::BITS && self.limbs[index as usize / 64] & (1 << (index % 64)) != 0
+    }
+
+    /// Adds a bit to the set.
+    pub fn set(&mut self, index: u32) {
+        self.limbs[index as usize / 64] |= 1 << (index % 64);
+    }
+
+    /// Removes a bit from the set.
+    pub fn unset(&mut self, index: u32) {
+        self.limbs[index as usize / 64] &= !(1 << (index % 64));
+    }
+
+    /// Whether the set contains no bits at all.
+    pub fn is_empty(&self) -> bool {
+        self.limbs.iter().all(|&limb| limb == 0)
+    }
+
+    /// The number of bits in the set.
+    pub fn count_ones(&self) -> u32 {
+        self.limbs.iter().map(|limb| limb.count_ones()).sum()
+    }
+
+    /// Iterates over the indices of the bits in the set, from low to high.
+    pub fn indices(self) -> impl Iterator<Item = u32> {
+        self.limbs.into_iter().enumerate().flat_map(|(i, limb)| {
+            let mut rest = limb;
+            std::iter::from_fn(move || {
+                if rest == 0 {
+                    return None;
+                }
+                let bit = rest.trailing_zeros();
+                // Clear the lowest set bit
+                rest &= rest - 1;
+                Some(i as u32 * 64 + bit)
+            })
+        })
+    }
+}
+
+impl<const LIMBS: usize> Default for WideBitboard<LIMBS> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<const LIMBS: usize> BitAnd for WideBitboard<LIMBS> {
+    type Output = Self;
+
+    fn bitand(mut self, rhs: Self) -> Self {
+        self.limbs.iter_mut().zip(rhs.limbs).for_each(|(limb, other)| *limb &= other);
+        self
+    }
+}
+
+impl<const LIMBS: usize> BitOr for WideBitboard<LIMBS> {
+    type Output = Self;
+
+    fn bitor(mut self, rhs: Self) -> Self {
+        self.limbs.iter_mut().zip(rhs.limbs).for_each(|(limb, other)| *limb |= other);
+        self
+    }
+}
+
+impl<const LIMBS: usize> BitXor for WideBitboard<LIMBS> {
+    type Output = Self;
+
+    fn bitxor(mut self, rhs: Self) -> Self {
+        self.limbs.iter_mut().zip(rhs.limbs).for_each(|(limb, other)| *limb ^= other);
+        self
+    }
+}
+
+impl<const LIMBS: usize> Not for WideBitboard<LIMBS> {
+    type Output = Self;
+
+    fn not(mut self) -> Self {
+        self.limbs.iter_mut().for_each(|limb| *limb = !*limb);
+        self
+    }
+}
+
+// Shifting moves bits towards higher indices. Bits shifted past the last limb are lost.
+impl<const LIMBS: usize> Shl<u32> for WideBitboard<LIMBS> {
+    type Output = Self;
+
+    fn shl(self, amount: u32) -> Self {
+        let limb_shift = (amount / 64) as usize;
+        let bit_shift = amount % 64;
+        let mut result = Self::empty();
+        for i in limb_shift..LIMBS {
+            let source = i - limb_shift;
+            result.limbs[i] = self.limbs[source] << bit_shift;
+            if bit_shift > 0 && source > 0 {
+                result.limbs[i] |= self.limbs[source - 1] >> (64 - bit_shift);
+            }
+        }
+        result
+    }
+}
+
+// Shifting moves bits towards lower indices. Bits shifted past the first limb are lost.
+impl<const LIMBS: usize> Shr<u32> for WideBitboard<LIMBS> {
+    type Output = Self;
+
+    fn shr(self, amount: u32) -> Self {
+        let limb_shift = (amount / 64) as usize;
+        let bit_shift = amount % 64;
+        let mut result = Self::empty();
+        for i in 0..LIMBS.saturating_sub(limb_shift) {
+            let source = i + limb_shift;
+            result.limbs[i] = self.limbs[source] >> bit_shift;
+            if bit_shift > 0 && source + 1 < LIMBS {
+                result.limbs[i] |= self.limbs[source + 1] << (64 - bit_shift);
+            }
+        }
+        result
+    }
+}
+
+/// A board storing standard chess pieces as bitboards, like `BitboardBoard`, but for any board with at most
+/// `64 * LIMBS` squares. For example, a 10x8 board fits in 2 limbs, and a 12x12 board needs 3.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct WideBitboardBoard<const LIMBS: usize> {
+    width: u16,
+    height: u16,
+    pieces: [WideBitboard<LIMBS>; 6],
+    colors: [WideBitboard<LIMBS>; 2],
+    // Every square that is on the board.
+    full: WideBitboard<LIMBS>,
+    // The squares on the first and last file.
+    first_file: WideBitboard<LIMBS>,
+    last_file: WideBitboard<LIMBS>,
+}
+
+impl<const LIMBS: usize> WideBitboardBoard<LIMBS> {
+    /// Creates a new empty board.
+    ///
+    /// Returns Err(_) if the board has no squares, or more squares than fit in the bitboards.
+    pub fn new(width: u16, height: u16) -> anyhow::Result<Self> {
+        let squares = width as u32 * height as u32;
+        ensure!(squares > 0, "Board must have at least one square");
+        ensure!(squares <= WideBitboard::<LIMBS>::BITS,
+            "Board with size ({width}, {height}) does not fit in {LIMBS} limbs of 64 bits");
+
+        let mut first_file = WideBitboard::empty();
+        let mut last_file = WideBitboard::empty();
+        for row in 0..height as u32 {
+            first_file.set(row * width as u32);
+            last_file.set(row * width as u32 + width as u32 - 1);
+        }
+        Ok(Self {
+            width,
+            height,
+            pieces: [WideBitboard::empty(); 6],
+            colors: [WideBitboard::empty(); 2],
+            full: WideBitboard::lowest(squares),
+            first_file,
+            last_file,
+        })
+    }
+
+    /// Copies the pieces of any other board with the same size.
+    pub fn from_board<B: Board<Token = ColoredStandardPiece>>(board: &B) -> anyhow::Result<Self> {
+        let mut wide = Self::new(board.width(), board.height())?;
+        for (square, token) in board.iter() {
+            wide.set(square, Some(token.clone()))?;
+        }
+        Ok(wide)
+    }
+
+    /// The bit belonging to a square, or `None` if the square is not on the board.
+    pub fn index(&self, square: Square) -> Option<u32> {
+        if square.row >= self.height || square.column >= self.width {
+            return None;
+        }
+        Some(square.row as u32 * self.width as u32 + square.column as u32)
+    }
+
+    /// The square belonging to a bit.
+    pub fn square(&self, index: u32) -> Square {
+        Square::new((index / self.width as u32) as u16, (index % self.width as u32) as u16)
+    }
+
+    /// Iterates over the squares in a set, from the first row to the last.
+    pub fn squares_in(&self, set: WideBitboard<LIMBS>) -> impl Iterator<Item = Square> {
+        let width = self.width as u32;
+        set.indices().map(move |index| Square::new((index / width) as u16, (index % width) as u16))
+    }
+
+    /// The set of all squares on the board.
+    pub fn full(&self) -> WideBitboard<LIMBS> {
+        self.full
+    }
+
+    /// The set of all occupied squares.
+    pub fn occupancy(&self) -> WideBitboard<LIMBS> {
+        self.colors[0] | self.colors[1]
+    }
+
+    /// The set of squares occupied by pieces of the given color.
+    pub fn color(&self, color: PieceColor) -> WideBitboard<LIMBS> {
+        self.colors[color_index(color)]
+    }
+
+    /// The set of squares occupied by the given piece type, of either color.
+    pub fn piece(&self, piece: StandardPiece) -> WideBitboard<LIMBS> {
+        self.pieces[piece_index(piece)]
+    }
+
+    /// The set of squares occupied by the given piece type of the given color.
+    pub fn pieces(&self, piece: StandardPiece, color: PieceColor) -> WideBitboard<LIMBS> {
+        self.piece(piece) & self.color(color)
+    }
+
+    /// The number of pieces of the given type and color on the board.
+    pub fn count(&self, piece: StandardPiece, color: PieceColor) -> u32 {
+        self.pieces(piece, color).count_ones()
+    }
+
+    /// Moves every square in the set by the given number of rows and columns.
+    /// Squares that would end up outside the board are dropped, instead of wrapping around to the next row.
+    pub fn shift(&self, set: WideBitboard<LIMBS>, rows: i32, columns: i32) -> WideBitboard<LIMBS> {
+        let mut set = set & self.full;
+        // Step one column at a time, dropping the squares on the file that would wrap around each time.
+        for _ in 0..columns.unsigned_abs().min(self.width as u32) {
+            set = if columns > 0 {
+                (set & !self.last_file) << 1
+            } else {
+                (set & !self.first_file) >> 1
+            };
+        }
+        if columns.unsigned_abs() >= self.width as u32 {
+            return WideBitboard::empty();
+        }
+
+        let distance = rows.unsigned_abs().saturating_mul(self.width as u32);
+        if distance >= WideBitboard::<LIMBS>::BITS {
+            return WideBitboard::empty();
+        }
+        let set = if rows > 0 { set << distance } else { set >> distance };
+        set & self.full
+    }
+
+    fn remove(&mut self, index: u32) {
+        for set in self.pieces.iter_mut().chain(self.colors.iter_mut()) {
+            set.unset(index);
+        }
+    }
+}
+
+impl<const LIMBS: usize> Board for WideBitboardBoard<LIMBS> {
+    type Token = ColoredStandardPiece;
+
+    fn width(&self) -> u16 {
+        self.width
+    }
+
+    fn height(&self) -> u16 {
+        self.height
+    }
+
+    fn valid_square(&self, square: Square) -> bool {
+        self.index(square).is_some()
+    }
+
+    fn get(&self, square: Square) -> Option<&Self::Token> {
+        let index = self.index(square)?;
+        let color = self.colors.iter().position(|set| set.contains(index))?;
+        let piece = self.pieces.iter().position(|set| set.contains(index))?;
+        Some(&TOKENS[color][piece])
+    }
+
+    fn set(&mut self, square: Square, token: Option<Self::Token>) -> anyhow::Result<()> {
+        let index = self.index(square).with_context(|| {
+            format!("Board index ({}, {}) is out of bounds for board with size ({}, {})", square.row, square.column, self.width, self.height)
+        })?;
+
+        self.remove(index);
+        if let Some(token) = token {
+            self.pieces[piece_index(token.piece())].set(index);
+            self.colors[color_index(token.color())].set(index);
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self) {
+        self.pieces = [WideBitboard::empty(); 6];
+        self.colors = [WideBitboard::empty(); 2];
+    }
+
+    fn make_move(&mut self, from: Square, to: Square) -> anyhow::Result<Option<Self::Token>> {
+        ensure!(self.valid_square(from), "Source square is not inside the board");
+        ensure!(self.valid_square(to), "Destination square is not inside the board");
+
+        let Some(token) = self.at(from) else { return Ok(None); };
+        if from == to {
+            return Ok(None);
+        }
+        let captured = self.at(to);
+        self.set(from, None)?;
+        self.set(to, Some(token))?;
+        Ok(captured)
+    }
+
+    fn occupied(&self) -> impl Iterator<Item = Square> {
+        self.squares_in(self.occupancy())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::test_rng::TestRng;
+    use crate::board::mailbox::MailboxBoard;
+    use crate::standard::perft::perft;
+    use crate::standard::position::StandardPosition;
+    use super::*;
+
+    #[test]
+    fn set_operations() {
+        let a = WideBitboard::<3>::bit(3) | WideBitboard::bit(70) | WideBitboard::bit(150);
+        assert_eq!(a.count_ones(), 3);
+        assert_eq!(a.indices().collect::<Vec<_>>(), [3, 70, 150]);
+        assert_eq!((a << 60).indices().collect::<Vec<_>>(), [63, 130]);
+        assert_eq!((a >> 4).indices().collect::<Vec<_>>(), [66, 146]);
+        assert_eq!((a << 128).indices().collect::<Vec<_>>(), [131]);
+        assert!((a << 192).is_empty());
+        assert_eq!((a & WideBitboard::lowest(71)).count_ones(), 2);
+        assert_eq!(WideBitboard::<3>::lowest(130).count_ones(), 130);
+        assert_eq!((!WideBitboard::<2>::empty()).count_ones(), 128);
+        assert!((a ^ a).is_empty());
+    }
+
+    #[test]
+    fn size_limits() {
+        assert!(WideBitboardBoard::<2>::new(10, 8).is_ok());
+        assert!(WideBitboardBoard::<2>::new(12, 12).is_err());
+        assert!(WideBitboardBoard::<3>::new(12, 12).is_ok());
+        assert!(WideBitboardBoard::<1>::new(0, 8).is_err());
+    }
+
+    #[test]
+    fn shifts_do_not_wrap_around() {
+        let board = WideBitboardBoard::<3>::new(12, 12).unwrap();
+        let bit = |name: &str| WideBitboard::bit(board.index(Square::parse(name).unwrap()).unwrap());
+        let names = |set: WideBitboard<3>| board.squares_in(set).map(|square| square.to_string()).collect::<Vec<_>>();
+
+        // l is the last file of a 12x12 board
+        let set = bit("a1") | bit("l1") | bit("f6") | bit("l12");
+        assert_eq!(names(board.shift(set, 0, 1)), ["b1", "g6"]);
+        assert_eq!(names(board.shift(set, 0, -1)), ["k1", "e6", "k12"]);
+        assert_eq!(names(board.shift(set, 1, 0)), ["a2", "l2", "f7"]);
+        assert_eq!(names(board.shift(set, -1, 2)), ["h5"]);
+        assert_eq!(names(board.shift(set, 6, -5)), ["g7", "a12"]);
+        assert!(board.shift(set, 0, 12).is_empty());
+        assert!(board.shift(set, -12, 0).is_empty());
+        assert_eq!(board.shift(board.full(), 1, 1).count_ones(), 11 * 11);
+    }
+
+    fn assert_equivalent<const LIMBS: usize>(width: u16, height: u16, seed: u64) {
+        let mut rng = TestRng::new(seed);
+        let mut wide = WideBitboardBoard::<LIMBS>::new(width, height).unwrap();
+        let mut mailbox = MailboxBoard::new(width, height);
+        let square = |rng: &mut TestRng| Square::new(rng.next(height + 2), rng.next(width + 2));
+
+        for step in 0..3000 {
+            if rng.next(2) == 0 {
+                let (square, token) = (square(&mut rng), rng.token());
+                assert_eq!(wide.set(square, token.clone()).is_ok(), mailbox.set(square, token).is_ok(), "step {step}");
+            } else {
+                let (from, to) = (square(&mut rng), square(&mut rng));
+                let expected = mailbox.make_move(from, to).ok();
+                assert_eq!(wide.make_move(from, to).ok(), expected, "step {step}: {from} to {to}");
+            }
+
+            let mut occupied = 0;
+            for row in 0..height + 2 {
+                for column in 0..width + 2 {
+                    let square = Square::new(row, column);
+                    assert_eq!(wide.valid_square(square), mailbox.valid_square(square), "{square}");
+                    assert_eq!(wide.at(square), mailbox.at(square), "step {step}: {square}");
+                    occupied += mailbox.at(square).is_some() as u32;
+                }
+            }
+            assert_eq!(wide.occupancy().count_ones(), occupied);
+            assert!(wide.occupied().eq(mailbox.occupied()));
+        }
+    }
+
+    #[test]
+    fn equivalent_to_mailbox() {
+        assert_equivalent::<1>(8, 8, 1);
+        assert_equivalent::<2>(10, 8, 2);
+        assert_equivalent::<3>(12, 12, 3);
+        assert_equivalent::<1>(3, 5, 4);
+    }
+
+    #[test]
+    fn same_perft_as_mailbox() {
+        // A 10x8 board like in Capablanca chess, with an extra bishop and knight instead of the archbishop and chancellor.
+        let mailbox = StandardPosition::from_fen("rnbqkbnrbn/pppppppppp/10/10/10/10/PPPPPPPPPP/RNBQKBNRBN w - - 0 1").unwrap();
+        let wide = StandardPosition::new(WideBitboardBoard::<2>::from_board(mailbox.board()).unwrap());
+        assert_eq!(perft(&wide, 2), perft(&mailbox, 2));
+        assert_eq!(wide.to_fen(), mailbox.to_fen());
+    }
+}
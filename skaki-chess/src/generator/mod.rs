@@ -0,0 +1,9 @@
+use crate::moves::Move;
+
+/// Represents an algorithm to enumerate all legal moves in a position.
+/// Like `MoveValidator`, this varies from variant to variant, so it is a trait we can swap out.
+pub trait MoveGenerator<P, M: Move> {
+    /// Returns every move that is legal in the given position, according to the rules of this move generator.
+    /// The order of the moves is unspecified.
+    fn generate(&self, position: &P) -> Vec<M>;
+}
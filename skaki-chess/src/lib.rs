@@ -4,7 +4,8 @@ pub mod board;
 pub mod square;
 pub mod moves;
 pub mod validator;
-mod standard;
+pub mod generator;
+pub mod standard;
 
 #[cfg(test)]
 mod tests {
@@ -1,4 +1,6 @@
-use anyhow::ensure;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use anyhow::{ensure, Context};
 
 #[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
 pub struct Square {
@@ -12,19 +14,131 @@ impl Square {
     }
 
     // Tries to parse a string into square.
-    // The string must start with a single letter from a-z denoting the column,
-    // followed by a number indicating the row.
+    // The string must start with one or more letters denoting the column, followed by a number indicating the row.
+    // Columns are named like spreadsheet columns: a-z, followed by aa, ab, ..., az, ba, and so on. Both lowercase
+    // and uppercase letters are accepted.
     // Note that unlike in code, rows are numbered starting from 1.
     pub fn parse(s: &str) -> anyhow::Result<Self> {
         ensure!(s.len() >= 2, "Valid square string must have at least two characters.");
-        let c = s.chars().nth(0).expect("Already ensured that the string has at least two characters.");
-        ensure!(c.is_ascii_lowercase(), "First character of square string must be a lowercase ascii character from a-z.");
-        // Find column from first character
-        let col = c.to_ascii_lowercase() as u16 - 'a' as u16;
+        let digits = s.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+        let letters = &s[..s.len() - digits.len()];
+        ensure!(!letters.is_empty(), "Square string must start with an ascii letter denoting the column.");
+        let column = Self::parse_file(letters)?;
         // Find row from the rest of the string. If parsing fails, return an error
-        let row: u16 = s[1..].parse()?;
+        let row: u16 = digits.parse()?;
         ensure!(row >= 1, "Square string must have a row number of at least 1");
         // The real row index is one lower, since rows are numbered starting from one in the notation format.
-        Ok(Self { row: row - 1, column: col })
+        Ok(Self { row: row - 1, column })
     }
-}
\ No newline at end of file
+
+    /// Parses the name of a file (such as `e` or `aa`) into a column index.
+    pub fn parse_file(file: &str) -> anyhow::Result<u16> {
+        ensure!(!file.is_empty(), "File name cannot be empty");
+        ensure!(file.chars().all(|c| c.is_ascii_alphabetic()), "File name '{file}' can only contain ascii letters");
+        // Files are numbered in bijective base 26, so `a` is 1 and `aa` is 27. The column index is one lower.
+        let mut number: u32 = 0;
+        for c in file.chars() {
+            let digit = c.to_ascii_lowercase() as u32 - 'a' as u32 + 1;
+            number = number * 26 + digit;
+            ensure!(number <= u16::MAX as u32 + 1, "File '{file}' is too large");
+        }
+        u16::try_from(number - 1).with_context(|| format!("File '{file}' is too large"))
+    }
+
+    /// The name of the file with the given column index, like `a` for the first column, or `aa` for the 27th.
+    pub fn file_name(column: u16) -> String {
+        let mut name = Vec::new();
+        let mut number = column as u32 + 1;
+        while number > 0 {
+            let digit = (number - 1) % 26;
+            name.push(b'a' + digit as u8);
+            number = (number - 1) / 26;
+        }
+        name.reverse();
+        String::from_utf8(name).expect("File names are ascii")
+    }
+
+    /// The name of the rank with the given row index. Ranks are numbered starting from 1.
+    pub fn rank_name(row: u16) -> String {
+        (row as u32 + 1).to_string()
+    }
+
+    /// The name of the file this square is on.
+    pub fn file(&self) -> String {
+        Self::file_name(self.column)
+    }
+
+    /// The name of the rank this square is on.
+    pub fn rank(&self) -> String {
+        Self::rank_name(self.row)
+    }
+
+    /// The square that is `rows` rows and `columns` columns away from this one.
+    /// Returns `None` if either index would become negative or too large. Note that this knows nothing about the
+    /// size of the board, so the square may still be outside of it.
+    pub fn offset(&self, rows: i32, columns: i32) -> Option<Square> {
+        let row = u16::try_from(self.row as i32 + rows).ok()?;
+        let column = u16::try_from(self.column as i32 + columns).ok()?;
+        Some(Self { row, column })
+    }
+}
+
+impl Display for Square {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.file(), self.rank())
+    }
+}
+
+impl FromStr for Square {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_squares() {
+        assert_eq!(Square::parse("a1").unwrap(), Square::new(0, 0));
+        assert_eq!(Square::parse("e4").unwrap(), Square::new(3, 4));
+        assert_eq!(Square::parse("E4").unwrap(), Square::new(3, 4));
+        assert_eq!(Square::parse("z26").unwrap(), Square::new(25, 25));
+        assert_eq!(Square::parse("aa10").unwrap(), Square::new(9, 26));
+        assert_eq!(Square::parse("ab1").unwrap(), Square::new(0, 27));
+        assert_eq!(Square::parse("ba3").unwrap(), Square::new(2, 52));
+        assert_eq!("h8".parse::<Square>().unwrap(), Square::new(7, 7));
+
+        for invalid in ["", "a", "4", "a0", "4a", "a-1", "é4", "a4b", "zzzzz1"] {
+            assert!(Square::parse(invalid).is_err(), "{invalid} should not parse");
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        assert_eq!(Square::new(3, 4).to_string(), "e4");
+        assert_eq!(Square::new(9, 26).to_string(), "aa10");
+        assert_eq!(Square::file_name(25), "z");
+        assert_eq!(Square::file_name(701), "zz");
+        assert_eq!(Square::file_name(702), "aaa");
+        assert_eq!(Square::rank_name(0), "1");
+        for column in (0..2000).chain([u16::MAX - 1, u16::MAX]) {
+            let square = Square::new(column / 3, column);
+            assert_eq!(square.to_string().parse::<Square>().unwrap(), square);
+            assert_eq!(Square::parse_file(&square.file()).unwrap(), column);
+        }
+    }
+
+    #[test]
+    fn offsets() {
+        let square = Square::new(1, 1);
+        assert_eq!(square.offset(2, -1), Some(Square::new(3, 0)));
+        assert_eq!(square.offset(0, 0), Some(square));
+        assert_eq!(square.offset(-2, 0), None);
+        assert_eq!(square.offset(0, -2), None);
+        assert_eq!(Square::new(u16::MAX, 0).offset(1, 0), None);
+    }
+}
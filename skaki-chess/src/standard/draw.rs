@@ -0,0 +1,150 @@
+use crate::board::Board;
+use crate::square::Square;
+use crate::standard::moves::StandardMove;
+use crate::standard::piece::{ColoredStandardPiece, PieceColor, StandardPiece};
+use crate::standard::position::{CastlingRights, StandardPosition};
+use crate::standard::validator::StandardMoveValidator;
+use crate::validator::MoveValidator;
+
+/// After this many halfmoves without a capture or pawn move, either player can claim a draw.
+pub const FIFTY_MOVE_RULE: u32 = 100;
+/// After this many halfmoves without a capture or pawn move, the game is drawn automatically.
+pub const SEVENTY_FIVE_MOVE_RULE: u32 = 150;
+/// Once the same position has occurred this many times, either player can claim a draw.
+pub const THREEFOLD_REPETITION: usize = 3;
+/// Once the same position has occurred this many times, the game is drawn automatically.
+pub const FIVEFOLD_REPETITION: usize = 5;
+
+/// Identifies a position for the repetition rules.
+/// Two positions are the same if the same pieces are on the same squares, the same player is to move, and both players
+/// have the same castling rights. The en passant square only counts if an en passant capture is actually possible,
+/// since otherwise the available moves are the same as without it.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct PositionKey {
+    tokens: Vec<Option<ColoredStandardPiece>>,
+    side_to_move: PieceColor,
+    castling_rights: CastlingRights,
+    en_passant: Option<Square>,
+}
+
+impl PositionKey {
+    pub fn new<B: Board<Token = ColoredStandardPiece> + Clone>(position: &StandardPosition<B>) -> Self {
+        let board = position.board();
+        let tokens = board.squares().map(|square| board.at(square)).collect();
+
+        Self {
+            tokens,
+            side_to_move: position.side_to_move(),
+            castling_rights: position.castling_rights(),
+            en_passant: position.en_passant().filter(|&square| can_capture_en_passant(position, square)),
+        }
+    }
+}
+
+// Returns true if the player to move has a legal en passant capture onto the given square.
+fn can_capture_en_passant<B: Board<Token = ColoredStandardPiece> + Clone>(position: &StandardPosition<B>, square: Square) -> bool {
+    let validator = StandardMoveValidator {};
+    // The capturing pawn stands one row behind the en passant square, on either neighbouring column.
+    let row = match position.side_to_move() {
+        PieceColor::White => square.row.checked_sub(1),
+        PieceColor::Black => square.row.checked_add(1),
+    };
+    let Some(row) = row else { return false; };
+    [square.column.checked_sub(1), square.column.checked_add(1)]
+        .into_iter()
+        .flatten()
+        .map(|column| StandardMove::new(Square::new(row, column), square))
+        .any(|mov| validator.validate(position, &mov))
+}
+
+/// Returns true if neither player has enough material left to ever checkmate the other, no matter how badly the
+/// opponent plays. This covers a bare king against a king with at most a single minor piece, and positions where
+/// the only pieces left apart from the kings are bishops that all move on squares of the same color.
+pub fn has_insufficient_material<B: Board<Token = ColoredStandardPiece>>(board: &B) -> bool {
+    let mut minor_pieces = Vec::new();
+    for (square, piece) in board.iter() {
+        match piece.piece() {
+            StandardPiece::King => {}
+            StandardPiece::Knight | StandardPiece::Bishop => minor_pieces.push((piece.piece(), square)),
+            // A single pawn, rook or queen is always enough to be able to mate.
+            StandardPiece::Pawn | StandardPiece::Rook | StandardPiece::Queen => return false,
+        }
+    }
+
+    if minor_pieces.len() <= 1 {
+        return true;
+    }
+
+    // Bishops on squares of the same color can never cover the squares of the other color, no matter who they
+    // belong to. As soon as a knight is involved, a helpmate is possible.
+    let square_color = |square: Square| (square.row + square.column) % 2;
+    let (_, first) = minor_pieces[0];
+    minor_pieces.iter().all(|&(piece, square)| piece == StandardPiece::Bishop && square_color(square) == square_color(first))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::mailbox::MailboxBoard;
+    use super::*;
+
+    fn board_with(pieces: &[(&str, StandardPiece, PieceColor)]) -> MailboxBoard<ColoredStandardPiece> {
+        let mut board = MailboxBoard::new(8, 8);
+        for &(square, piece, color) in pieces {
+            board.set(Square::parse(square).unwrap(), Some(ColoredStandardPiece::new(piece, color))).unwrap();
+        }
+        board
+    }
+
+    #[test]
+    fn insufficient_material() {
+        let kings = [("e1", StandardPiece::King, PieceColor::White), ("e8", StandardPiece::King, PieceColor::Black)];
+        let with = |extra: &[(&str, StandardPiece, PieceColor)]| {
+            let pieces: Vec<_> = kings.iter().chain(extra.iter()).copied().collect();
+            has_insufficient_material(&board_with(&pieces))
+        };
+
+        assert!(with(&[]));
+        assert!(with(&[("c3", StandardPiece::Knight, PieceColor::White)]));
+        assert!(with(&[("c3", StandardPiece::Bishop, PieceColor::Black)]));
+        // Bishops on the same color, also when they belong to different players
+        assert!(with(&[("c1", StandardPiece::Bishop, PieceColor::White), ("f8", StandardPiece::Bishop, PieceColor::Black)]));
+        assert!(with(&[("c1", StandardPiece::Bishop, PieceColor::White), ("e3", StandardPiece::Bishop, PieceColor::White)]));
+
+        // Bishops on different colors can mate
+        assert!(!with(&[("c1", StandardPiece::Bishop, PieceColor::White), ("c8", StandardPiece::Bishop, PieceColor::Black)]));
+        // So can two knights, or a knight and a bishop, at least in theory
+        assert!(!with(&[("c3", StandardPiece::Knight, PieceColor::White), ("c6", StandardPiece::Knight, PieceColor::Black)]));
+        assert!(!with(&[("c3", StandardPiece::Knight, PieceColor::White), ("c1", StandardPiece::Bishop, PieceColor::White)]));
+        // Any pawn, rook or queen is enough
+        assert!(!with(&[("a2", StandardPiece::Pawn, PieceColor::White)]));
+        assert!(!with(&[("a8", StandardPiece::Rook, PieceColor::Black)]));
+        assert!(!with(&[("d1", StandardPiece::Queen, PieceColor::White)]));
+    }
+
+    #[test]
+    fn position_key_en_passant() {
+        let mut position = StandardPosition::starting_position();
+        let play = |position: &mut StandardPosition<MailboxBoard<ColoredStandardPiece>>, from: &str, to: &str| {
+            position.make_move(&StandardMove::new(Square::parse(from).unwrap(), Square::parse(to).unwrap())).unwrap();
+        };
+
+        // After a double step that cannot be captured, the en passant square does not matter
+        play(&mut position, "e2", "e4");
+        let mut without = position.clone();
+        without.set_en_passant(None);
+        assert_eq!(PositionKey::new(&position), PositionKey::new(&without));
+
+        // But if it can be captured, it is a different position
+        play(&mut position, "d7", "d5");
+        play(&mut position, "e4", "e5");
+        play(&mut position, "f7", "f5");
+        let mut without = position.clone();
+        without.set_en_passant(None);
+        assert_ne!(PositionKey::new(&position), PositionKey::new(&without));
+
+        // Side to move is part of the key
+        let mut other_side = without.clone();
+        other_side.set_side_to_move(PieceColor::Black);
+        assert_ne!(PositionKey::new(&without), PositionKey::new(&other_side));
+    }
+}
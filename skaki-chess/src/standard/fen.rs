@@ -0,0 +1,435 @@
+use anyhow::{bail, ensure, Context};
+use crate::board::Board;
+use crate::board::mailbox::MailboxBoard;
+use crate::square::Square;
+use crate::standard::piece::{ColoredStandardPiece, PieceColor, StandardPiece};
+use crate::standard::position::{CastlingRights, CastlingSide, StandardPosition};
+
+/// The FEN string of the starting position of a regular game of chess.
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+impl StandardPosition<MailboxBoard<ColoredStandardPiece>> {
+    /// Parses a position in Forsyth-Edwards Notation.
+    /// The size of the board is taken from the piece placement, so boards other than 8x8 are supported as well.
+    /// Empty squares are counted with (possibly multi-digit) numbers, so a rank of a 10 wide board can be written as `10`.
+    /// The halfmove clock and fullmove number may be left out, in which case they default to 0 and 1.
+    ///
+    /// Castling rights can be written as in regular FEN (`KQkq`), in Shredder-FEN with the file of the rook (`HAha`),
+    /// or in X-FEN which mixes both. This way Chess960 positions can be read without ambiguity. Note that `K`, `Q`,
+    /// `k` and `q` are always read as regular castling rights, and never as a file.
+    pub fn from_fen(fen: &str) -> anyhow::Result<Self> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        ensure!(fields.len() == 4 || fields.len() == 6, "FEN string must have 4 or 6 fields, but found {}", fields.len());
+
+        let board = parse_placement(fields[0])?;
+        let mut position = StandardPosition::new(board);
+        position.set_side_to_move(match fields[1] {
+            "w" => PieceColor::White,
+            "b" => PieceColor::Black,
+            other => bail!("Side to move must be 'w' or 'b', but found '{other}'"),
+        });
+        let rights = parse_castling(&position, fields[2])?;
+        position.set_castling_rights(rights);
+        let en_passant = parse_en_passant(&position, fields[3])?;
+        position.set_en_passant(en_passant);
+
+        if fields.len() == 6 {
+            let halfmove_clock = fields[4].parse()
+                .with_context(|| format!("Halfmove clock must be a non-negative number, but found '{}'", fields[4]))?;
+            let fullmove_number = fields[5].parse()
+                .with_context(|| format!("Fullmove number must be a positive number, but found '{}'", fields[5]))?;
+            ensure!(fullmove_number >= 1, "Fullmove number must be at least 1");
+            position.set_halfmove_clock(halfmove_clock);
+            position.set_fullmove_number(fullmove_number);
+        }
+
+        Ok(position)
+    }
+}
+
+// The ways castling rights can be written.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum CastlingNotation {
+    // X-FEN uses `KQkq` for the outermost rook, and only falls back to the file of the rook if that is ambiguous.
+    XFen,
+    // Shredder-FEN always uses the file of the rook.
+    Shredder,
+}
+
+impl<B: Board<Token = ColoredStandardPiece>> StandardPosition<B> {
+    /// Writes this position in Forsyth-Edwards Notation, including all six fields.
+    /// Castling rights are written in X-FEN, which is the same as regular FEN unless a player can castle with a rook
+    /// that is not the outermost one on that side, such as in some Chess960 positions.
+    pub fn to_fen(&self) -> String {
+        self.write_fen(CastlingNotation::XFen)
+    }
+
+    /// Writes this position in Shredder-FEN. This is the same as regular FEN, except that the castling rights are
+    /// written as the files of the rooks, e.g. `HAha` in the starting position.
+    pub fn to_shredder_fen(&self) -> String {
+        self.write_fen(CastlingNotation::Shredder)
+    }
+
+    fn write_fen(&self, notation: CastlingNotation) -> String {
+        let side = match self.side_to_move() {
+            PieceColor::White => "w",
+            PieceColor::Black => "b",
+        };
+        let en_passant = self.en_passant().map(|square| square.to_string()).unwrap_or_else(|| "-".to_string());
+        format!("{} {} {} {} {} {}", write_placement(self.board()), side, write_castling(self, notation),
+                en_passant, self.halfmove_clock(), self.fullmove_number())
+    }
+}
+
+// The letter of a piece in FEN, uppercase for white and lowercase for black.
+fn piece_letter(piece: &ColoredStandardPiece) -> char {
+    match piece.color() {
+        PieceColor::White => piece.piece().letter(),
+        PieceColor::Black => piece.piece().letter().to_ascii_lowercase(),
+    }
+}
+
+fn parse_placement(placement: &str) -> anyhow::Result<MailboxBoard<ColoredStandardPiece>> {
+    // Ranks are listed from the top of the board (black's side) to the bottom, so the first one has the highest
+    // number.
+    let fields: Vec<&str> = placement.split('/').collect();
+    let height = u16::try_from(fields.len()).context("Piece placement has too many ranks")?;
+    let ranks: Vec<Vec<Option<ColoredStandardPiece>>> = fields.into_iter()
+        .enumerate()
+        .map(|(index, rank)| parse_rank(rank).with_context(|| format!("Invalid rank {} in piece placement", height - index as u16)))
+        .collect::<anyhow::Result<_>>()?;
+
+    let width = ranks[0].len();
+    for (index, rank) in ranks.iter().enumerate() {
+        ensure!(rank.len() == width, "Rank {} has {} squares, but rank {} has {}", height - index as u16, rank.len(), height, width);
+    }
+    let width = u16::try_from(width).context("Piece placement has too many files")?;
+
+    let mut board = MailboxBoard::new(width, height);
+    for (index, rank) in ranks.into_iter().enumerate() {
+        let row = height - 1 - index as u16;
+        for (column, token) in rank.into_iter().enumerate() {
+            board.set(Square::new(row, column as u16), token).expect("Index in range");
+        }
+    }
+
+    Ok(board)
+}
+
+fn parse_rank(rank: &str) -> anyhow::Result<Vec<Option<ColoredStandardPiece>>> {
+    ensure!(!rank.is_empty(), "Rank is empty");
+    let mut tokens = Vec::new();
+    let mut empty = 0usize;
+    for c in rank.chars() {
+        if let Some(digit) = c.to_digit(10) {
+            ensure!(empty != 0 || digit != 0, "Number of empty squares cannot start with 0");
+            empty = empty * 10 + digit as usize;
+            ensure!(empty <= u16::MAX as usize, "Number of empty squares is too large");
+            continue;
+        }
+
+        tokens.extend(std::iter::repeat_n(None, empty));
+        empty = 0;
+        let piece = StandardPiece::from_letter(c).with_context(|| format!("Unknown piece letter '{c}'"))?;
+        let color = if c.is_ascii_uppercase() { PieceColor::White } else { PieceColor::Black };
+        tokens.push(Some(ColoredStandardPiece::new(piece, color)));
+    }
+    tokens.extend(std::iter::repeat_n(None, empty));
+
+    Ok(tokens)
+}
+
+fn write_placement<B: Board<Token = ColoredStandardPiece>>(board: &B) -> String {
+    let mut ranks = Vec::with_capacity(board.height() as usize);
+    for row in (0..board.height()).rev() {
+        let mut rank = String::new();
+        let mut empty = 0;
+        for column in 0..board.width() {
+            match board.at(Square::new(row, column)) {
+                Some(piece) => {
+                    if empty > 0 {
+                        rank.push_str(&empty.to_string());
+                        empty = 0;
+                    }
+                    rank.push(piece_letter(&piece));
+                }
+                None => empty += 1,
+            }
+        }
+        if empty > 0 {
+            rank.push_str(&empty.to_string());
+        }
+        ranks.push(rank);
+    }
+
+    ranks.join("/")
+}
+
+fn parse_castling<B: Board<Token = ColoredStandardPiece>>(position: &StandardPosition<B>, castling: &str) -> anyhow::Result<CastlingRights> {
+    let mut rights = CastlingRights::none();
+    if castling == "-" {
+        return Ok(rights);
+    }
+
+    for c in castling.chars() {
+        let (color, side, column) = match c {
+            'K' | 'Q' | 'k' | 'q' => {
+                let color = if c.is_ascii_uppercase() { PieceColor::White } else { PieceColor::Black };
+                let side = if c.eq_ignore_ascii_case(&'K') { CastlingSide::KingSide } else { CastlingSide::QueenSide };
+                let column = outermost_rook(position, color, side)
+                    .with_context(|| format!("Castling right '{c}' requires a king with a rook on that side on the back rank"))?;
+                (color, side, column)
+            }
+            // Any other letter is the file of the rook, as used by Shredder-FEN and X-FEN.
+            c if c.is_ascii_alphabetic() => {
+                let color = if c.is_ascii_uppercase() { PieceColor::White } else { PieceColor::Black };
+                let column = c.to_ascii_lowercase() as u16 - 'a' as u16;
+                let side = castling_side_of_rook(position, color, column)
+                    .with_context(|| format!("Castling right '{c}' requires a king and a rook on that file on the back rank"))?;
+                (color, side, column)
+            }
+            _ => bail!("Unknown castling right '{c}', expected 'KQkq', rook files or '-'"),
+        };
+        ensure!(!rights.has(color, side), "Castling right '{c}' is listed more than once for the same side");
+        rights.set(color, side, Some(column));
+    }
+
+    Ok(rights)
+}
+
+fn write_castling<B: Board<Token = ColoredStandardPiece>>(position: &StandardPosition<B>, notation: CastlingNotation) -> String {
+    let mut castling = String::new();
+    for color in [PieceColor::White, PieceColor::Black] {
+        for side in [CastlingSide::KingSide, CastlingSide::QueenSide] {
+            let Some(column) = position.castling_rights().rook_column(color, side) else { continue; };
+            let letter = if notation == CastlingNotation::XFen && outermost_rook(position, color, side) == Some(column) {
+                match side {
+                    CastlingSide::KingSide => "k".to_string(),
+                    CastlingSide::QueenSide => "q".to_string(),
+                }
+            } else {
+                Square::file_name(column)
+            };
+
+            castling.push_str(&match color {
+                PieceColor::White => letter.to_ascii_uppercase(),
+                PieceColor::Black => letter,
+            });
+        }
+    }
+
+    if castling.is_empty() {
+        castling.push('-');
+    }
+    castling
+}
+
+// Finds out on which side of the king a rook in the given column of the back rank is.
+// Returns `None` if there is no king or no such rook on the back rank.
+fn castling_side_of_rook<B: Board<Token = ColoredStandardPiece>>(position: &StandardPosition<B>, color: PieceColor, column: u16) -> Option<CastlingSide> {
+    let board = position.board();
+    let row = position.back_rank(color);
+    let rook = ColoredStandardPiece::new(StandardPiece::Rook, color);
+    if board.at(Square::new(row, column)) != Some(rook) {
+        return None;
+    }
+
+    let king_column = king_column(position, color)?;
+    Some(if column > king_column { CastlingSide::KingSide } else { CastlingSide::QueenSide })
+}
+
+// The column of the king of the given color, if it is on its back rank.
+fn king_column<B: Board<Token = ColoredStandardPiece>>(position: &StandardPosition<B>, color: PieceColor) -> Option<u16> {
+    let board = position.board();
+    let row = position.back_rank(color);
+    let king = ColoredStandardPiece::new(StandardPiece::King, color);
+    (0..board.width()).find(|&column| board.at(Square::new(row, column)).as_ref() == Some(&king))
+}
+
+// Finds the column of the rook furthest away from the king on the given side of the back rank.
+// This is the rook that a regular castling right (such as `K` in FEN) refers to.
+fn outermost_rook<B: Board<Token = ColoredStandardPiece>>(position: &StandardPosition<B>, color: PieceColor, side: CastlingSide) -> Option<u16> {
+    let board = position.board();
+    let row = position.back_rank(color);
+    let rook = ColoredStandardPiece::new(StandardPiece::Rook, color);
+    let king_column = king_column(position, color)?;
+
+    let is_rook = |column: &u16| board.at(Square::new(row, *column)).as_ref() == Some(&rook);
+    match side {
+        CastlingSide::KingSide => (king_column + 1..board.width()).rev().find(is_rook),
+        CastlingSide::QueenSide => (0..king_column).find(is_rook),
+    }
+}
+
+fn parse_en_passant<B: Board<Token = ColoredStandardPiece>>(position: &StandardPosition<B>, en_passant: &str) -> anyhow::Result<Option<Square>> {
+    if en_passant == "-" {
+        return Ok(None);
+    }
+
+    let square = Square::parse(en_passant).with_context(|| format!("Invalid en passant square '{en_passant}'"))?;
+    let board = position.board();
+    ensure!(board.valid_square(square), "En passant square '{en_passant}' is not on the board");
+    // The square is skipped by a double step of the player that just moved.
+    let expected_row = match position.side_to_move() {
+        PieceColor::White => board.height().checked_sub(3),
+        PieceColor::Black => Some(2),
+    };
+    ensure!(Some(square.row) == expected_row, "En passant square '{en_passant}' is not on the row behind a pawn double step");
+
+    Ok(Some(square))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generator::MoveGenerator;
+    use crate::standard::generator::StandardMoveGenerator;
+    use super::*;
+
+    #[test]
+    fn starting_position() {
+        let position = StandardPosition::from_fen(STARTING_FEN).unwrap();
+        let expected = StandardPosition::starting_position();
+        assert_eq!(position.to_fen(), STARTING_FEN);
+        assert_eq!(expected.to_fen(), STARTING_FEN);
+        assert_eq!(position.castling_rights(), expected.castling_rights());
+        for row in 0..8 {
+            for column in 0..8 {
+                let square = Square::new(row, column);
+                assert_eq!(position.board().at(square), expected.board().at(square));
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 57 103",
+        ] {
+            assert_eq!(StandardPosition::from_fen(fen).unwrap().to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn optional_clocks() {
+        let position = StandardPosition::from_fen("8/8/8/8/8/8/8/K6k w - -").unwrap();
+        assert_eq!(position.halfmove_clock(), 0);
+        assert_eq!(position.fullmove_number(), 1);
+    }
+
+    #[test]
+    fn state_fields() {
+        let position = StandardPosition::from_fen("r3k3/8/8/3pP3/8/8/8/4K2R w Kq d6 3 20").unwrap();
+        assert_eq!(position.side_to_move(), PieceColor::White);
+        assert!(position.castling_rights().has(PieceColor::White, CastlingSide::KingSide));
+        assert!(!position.castling_rights().has(PieceColor::White, CastlingSide::QueenSide));
+        assert_eq!(position.castling_rights().rook_column(PieceColor::Black, CastlingSide::QueenSide), Some(0));
+        assert_eq!(position.en_passant(), Some(Square::parse("d6").unwrap()));
+        assert_eq!(position.halfmove_clock(), 3);
+        assert_eq!(position.fullmove_number(), 20);
+    }
+
+    #[test]
+    fn larger_board() {
+        // A 10x8 board, like in Capablanca chess
+        let fen = "rnbqkbnrrr/pppppppppp/10/10/10/10/PPPPPPPPPP/RNBQKBNRRR w KQkq - 0 1";
+        let position = StandardPosition::from_fen(fen).unwrap();
+        assert_eq!(position.board().width(), 10);
+        assert_eq!(position.board().height(), 8);
+        assert_eq!(position.board().at(Square::parse("j1").unwrap()), Some(ColoredStandardPiece::new(StandardPiece::Rook, PieceColor::White)));
+        // The king side castling right refers to the outermost rook
+        assert_eq!(position.castling_rights().rook_column(PieceColor::White, CastlingSide::KingSide), Some(9));
+        assert_eq!(position.to_fen(), fen);
+
+        // Boards do not have to be square
+        let position = StandardPosition::from_fen("k2/3/3/3/3/3/2K w - -").unwrap();
+        assert_eq!(position.board().width(), 3);
+        assert_eq!(position.board().height(), 7);
+    }
+
+    #[test]
+    fn shredder_fen() {
+        let position = StandardPosition::from_fen(STARTING_FEN).unwrap();
+        let shredder = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1";
+        assert_eq!(position.to_shredder_fen(), shredder);
+        let parsed = StandardPosition::from_fen(shredder).unwrap();
+        assert_eq!(parsed.castling_rights(), position.castling_rights());
+        assert_eq!(parsed.to_fen(), STARTING_FEN);
+
+        // A Chess960 starting position
+        let fen = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9";
+        let position = StandardPosition::from_fen(fen).unwrap();
+        assert_eq!(position.castling_rights().rook_column(PieceColor::White, CastlingSide::KingSide), Some(7));
+        assert_eq!(position.castling_rights().rook_column(PieceColor::White, CastlingSide::QueenSide), Some(5));
+        assert_eq!(position.to_shredder_fen(), fen);
+        assert_eq!(position.to_fen(), "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9");
+    }
+
+    #[test]
+    fn xfen() {
+        // Both white rooks are on the king side, so the inner one needs its file to be written out.
+        let fen = "rk2r3/8/8/8/8/8/8/1K2R2R w Ea - 0 1";
+        let position = StandardPosition::from_fen(fen).unwrap();
+        assert_eq!(position.castling_rights().rook_column(PieceColor::White, CastlingSide::QueenSide), None);
+        assert_eq!(position.castling_rights().rook_column(PieceColor::White, CastlingSide::KingSide), Some(4));
+        assert_eq!(position.to_fen(), "rk2r3/8/8/8/8/8/8/1K2R2R w Eq - 0 1");
+        assert_eq!(position.to_shredder_fen(), "rk2r3/8/8/8/8/8/8/1K2R2R w Ea - 0 1");
+
+        // The outermost rook is written as a regular castling right
+        let fen = "rk2r3/8/8/8/8/8/8/1K2R2R w Kq - 0 1";
+        let position = StandardPosition::from_fen(fen).unwrap();
+        assert_eq!(position.castling_rights().rook_column(PieceColor::White, CastlingSide::KingSide), Some(7));
+        assert_eq!(position.to_fen(), fen);
+        assert_eq!(StandardPosition::from_fen(&position.to_shredder_fen()).unwrap().to_fen(), fen);
+    }
+
+    #[test]
+    fn chess960_castling() {
+        // White castles king side with the inner rook on e1, black castles queen side with the king on b8.
+        let mut position = StandardPosition::from_fen("rk5r/8/8/8/8/8/8/1K2R2R w Ea - 0 1").unwrap();
+        let generator = StandardMoveGenerator {};
+        for (san, fen) in [
+            ("O-O", "rk5r/8/8/8/8/8/8/5RKR b q - 1 1"),
+            ("O-O-O", "2kr3r/8/8/8/8/8/8/5RKR w - - 2 2"),
+        ] {
+            let mov = position.parse_san(san).unwrap();
+            assert!(generator.generate(&position).contains(&mov));
+            position.make_move(&mov).unwrap();
+            assert_eq!(position.to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn malformed_input() {
+        let error = |fen: &str| format!("{:#}", StandardPosition::from_fen(fen).unwrap_err());
+
+        assert!(error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq").contains("4 or 6 fields"));
+        assert!(error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPP/RNBQKBNR w KQkq - 0 1").contains("Rank 2 has 7 squares"));
+        assert!(error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1").contains("Unknown piece letter 'X'"));
+        assert!(error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1").contains("Side to move"));
+        assert!(error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQk1 - 0 1").contains("Unknown castling right '1'"));
+        assert!(error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KKkq - 0 1").contains("more than once"));
+        assert!(error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KHkq - 0 1").contains("more than once"));
+        assert!(error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w GAha - 0 1").contains("Castling right 'G'"));
+        assert!(error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w Z - 0 1").contains("Castling right 'Z'"));
+        assert!(error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN1 w KQkq - 0 1").contains("Castling right 'K'"));
+        assert!(error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e4 0 1").contains("En passant square 'e4'"));
+        assert!(error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1").contains("Halfmove clock"));
+        assert!(error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 0").contains("Fullmove number"));
+        assert!(error("rnbqkbnr//8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").contains("Invalid rank 7"));
+    }
+
+    #[test]
+    fn errors_name_the_rank() {
+        let error = |fen: &str| format!("{:#}", StandardPosition::from_fen(fen).unwrap_err());
+
+        assert_eq!(error("rnbqkbnr/ppppXppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            "Invalid rank 7 in piece placement: Unknown piece letter 'X'");
+        assert_eq!(error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN w KQkq - 0 1"),
+            "Rank 1 has 7 squares, but rank 8 has 8");
+        assert_eq!(error("rnbqkbnr/pppppppp/8/8/PPPPPPPP/RNBQKBN w - - 0 1"),
+            "Rank 1 has 7 squares, but rank 6 has 8");
+    }
+}
@@ -0,0 +1,205 @@
+use anyhow::ensure;
+use crate::board::Board;
+use crate::standard::draw::{PositionKey, FIVEFOLD_REPETITION, THREEFOLD_REPETITION};
+use crate::standard::moves::StandardMove;
+use crate::standard::piece::ColoredStandardPiece;
+use crate::standard::position::{StandardPosition, Undo};
+use crate::standard::result::{claimable_draw, game_result, DrawReason, GameResult};
+use crate::standard::validator::StandardMoveValidator;
+use crate::validator::MoveValidator;
+
+/// A standard game of chess in progress. Next to the current position, this keeps track of the moves played so far
+/// and every position that occurred, which is needed for the repetition rules.
+#[derive(Debug, Clone)]
+pub struct StandardGame<B: Board<Token = ColoredStandardPiece>> {
+    position: StandardPosition<B>,
+    moves: Vec<StandardMove>,
+    // The undo record of every move played, so moves can be taken back.
+    undos: Vec<Undo>,
+    // The key of every position in the game, including the starting position and the current one.
+    history: Vec<PositionKey>,
+    // The result of the game in the current position. This needs all legal moves, so it is only worked out once
+    // after every move instead of every time it is asked for.
+    result: Option<GameResult>,
+}
+
+impl<B: Board<Token = ColoredStandardPiece> + Clone> StandardGame<B> {
+    /// Starts a new game from the given position.
+    pub fn new(position: StandardPosition<B>) -> Self {
+        let history = vec![PositionKey::new(&position)];
+        let mut game = Self {
+            position,
+            moves: Vec::new(),
+            undos: Vec::new(),
+            history,
+            result: None,
+        };
+        game.result = game.compute_result();
+        game
+    }
+
+    /// The current position of the game.
+    pub fn position(&self) -> &StandardPosition<B> {
+        &self.position
+    }
+
+    /// All moves played so far, in order.
+    pub fn moves(&self) -> &[StandardMove] {
+        &self.moves
+    }
+
+    /// Plays a move in the current position.
+    ///
+    /// Returns Err(_) if the move is not legal, or the game is already over.
+    pub fn make_move(&mut self, mov: &StandardMove) -> anyhow::Result<()> {
+        ensure!(self.result().is_none(), "The game is already over");
+        let validator = StandardMoveValidator {};
+        ensure!(validator.validate(&self.position, mov), "Move is not legal in the current position");
+
+        let undo = self.position.make(mov)?;
+        self.moves.push(*mov);
+        self.undos.push(undo);
+        self.history.push(PositionKey::new(&self.position));
+        self.result = self.compute_result();
+        Ok(())
+    }
+
+    /// Takes back the last move played, returning it. Returns `None` if no moves were played yet.
+    pub fn takeback(&mut self) -> Option<StandardMove> {
+        let undo = self.undos.pop()?;
+        self.position.unmake(undo).expect("Undo records always belong to the current position");
+        self.history.pop();
+        self.result = self.compute_result();
+        self.moves.pop()
+    }
+
+    /// The number of times the current position has occurred in this game, including right now.
+    pub fn repetitions(&self) -> usize {
+        let current = self.history.last().expect("The history always contains the current position");
+        self.history.iter().filter(|&key| key == current).count()
+    }
+
+    /// Determines whether the game is over, and if so, what the result is.
+    /// Draws that have to be claimed do not end the game, see `claimable_draw` for those.
+    pub fn result(&self) -> Option<GameResult> {
+        self.result
+    }
+
+    fn compute_result(&self) -> Option<GameResult> {
+        if let Some(result) = game_result(&self.position) {
+            return Some(result);
+        }
+
+        (self.repetitions() >= FIVEFOLD_REPETITION).then_some(GameResult::Draw(DrawReason::FivefoldRepetition))
+    }
+
+    /// Returns the reason the player to move can claim a draw right now, if there is one.
+    pub fn claimable_draw(&self) -> Option<DrawReason> {
+        if self.repetitions() >= THREEFOLD_REPETITION {
+            return Some(DrawReason::ThreefoldRepetition);
+        }
+
+        claimable_draw(&self.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::mailbox::MailboxBoard;
+    use crate::square::Square;
+    use super::*;
+
+    fn play(game: &mut StandardGame<MailboxBoard<ColoredStandardPiece>>, from: &str, to: &str) {
+        let mov = StandardMove::new(Square::parse(from).unwrap(), Square::parse(to).unwrap());
+        game.make_move(&mov).expect("Move should be legal");
+    }
+
+    // Moves both knights out and back again, repeating the starting position.
+    fn knight_dance(game: &mut StandardGame<MailboxBoard<ColoredStandardPiece>>) {
+        for (from, to) in [("g1", "f3"), ("g8", "f6"), ("f3", "g1"), ("f6", "g8")] {
+            play(game, from, to);
+        }
+    }
+
+    #[test]
+    fn rejects_illegal_moves() {
+        let mut game = StandardGame::new(StandardPosition::starting_position());
+        let mov = StandardMove::new(Square::parse("e2").unwrap(), Square::parse("e5").unwrap());
+        assert!(game.make_move(&mov).is_err());
+        assert!(game.moves().is_empty());
+
+        play(&mut game, "e2", "e4");
+        assert_eq!(game.moves().len(), 1);
+    }
+
+    #[test]
+    fn repetition() {
+        let mut game = StandardGame::new(StandardPosition::starting_position());
+        assert_eq!(game.repetitions(), 1);
+
+        knight_dance(&mut game);
+        assert_eq!(game.repetitions(), 2);
+        assert_eq!(game.claimable_draw(), None);
+
+        knight_dance(&mut game);
+        assert_eq!(game.repetitions(), 3);
+        assert_eq!(game.claimable_draw(), Some(DrawReason::ThreefoldRepetition));
+        assert_eq!(game.result(), None);
+
+        knight_dance(&mut game);
+        knight_dance(&mut game);
+        assert_eq!(game.repetitions(), 5);
+        assert_eq!(game.result(), Some(GameResult::Draw(DrawReason::FivefoldRepetition)));
+
+        // No more moves can be played once the game is over
+        let mov = StandardMove::new(Square::parse("e2").unwrap(), Square::parse("e4").unwrap());
+        assert!(game.make_move(&mov).is_err());
+    }
+
+    #[test]
+    fn takeback() {
+        let mut game = StandardGame::new(StandardPosition::starting_position());
+        assert_eq!(game.takeback(), None);
+
+        knight_dance(&mut game);
+        knight_dance(&mut game);
+        assert_eq!(game.repetitions(), 3);
+
+        let last = *game.moves().last().unwrap();
+        assert_eq!(game.takeback(), Some(last));
+        assert_eq!(game.moves().len(), 7);
+        assert_eq!(game.repetitions(), 2);
+        assert_eq!(game.position().side_to_move(), crate::standard::piece::PieceColor::Black);
+
+        // The move can be played again afterward
+        game.make_move(&last).unwrap();
+        assert_eq!(game.repetitions(), 3);
+
+        // Taking back a move that ended the game makes it playable again
+        let mut game = StandardGame::new(StandardPosition::starting_position());
+        for (from, to) in [("f2", "f3"), ("e7", "e5"), ("g2", "g4"), ("d8", "h4")] {
+            play(&mut game, from, to);
+        }
+        assert!(game.result().is_some());
+        game.takeback();
+        assert_eq!(game.result(), None);
+        play(&mut game, "d8", "h4");
+    }
+
+    #[test]
+    fn lost_castling_rights_change_position() {
+        let mut game = StandardGame::new(StandardPosition::starting_position());
+        play(&mut game, "e2", "e4");
+        play(&mut game, "e7", "e5");
+        // The kings step out and back, which returns the same pieces to the same squares but loses castling rights.
+        for (from, to) in [("e1", "e2"), ("e8", "e7"), ("e2", "e1"), ("e7", "e8")] {
+            play(&mut game, from, to);
+        }
+        assert_eq!(game.repetitions(), 1);
+
+        for (from, to) in [("e1", "e2"), ("e8", "e7"), ("e2", "e1"), ("e7", "e8")] {
+            play(&mut game, from, to);
+        }
+        assert_eq!(game.repetitions(), 2);
+    }
+}
@@ -0,0 +1,198 @@
+use crate::board::Board;
+use crate::generator::MoveGenerator;
+use crate::square::Square;
+use crate::standard::moves::StandardMove;
+use crate::standard::piece::{ColoredStandardPiece, PieceColor, StandardPiece};
+use crate::standard::position::{CastlingSide, StandardPosition};
+use crate::standard::validator::StandardMoveValidator;
+use crate::validator::MoveValidator;
+
+const KNIGHT_OFFSETS: [(i32, i32); 8] = [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)];
+const KING_OFFSETS: [(i32, i32); 8] = [(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)];
+const BISHOP_DIRECTIONS: [(i32, i32); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+const ROOK_DIRECTIONS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+const PROMOTIONS: [StandardPiece; 4] = [StandardPiece::Queen, StandardPiece::Rook, StandardPiece::Bishop, StandardPiece::Knight];
+
+/// The standard move generator, enumerates all legal moves according to the basic chess rules.
+/// Every move it produces is accepted by the `StandardMoveValidator`.
+pub struct StandardMoveGenerator {
+
+}
+
+impl<B: Board<Token = ColoredStandardPiece> + Clone> MoveGenerator<StandardPosition<B>, StandardMove> for StandardMoveGenerator {
+    fn generate(&self, position: &StandardPosition<B>) -> Vec<StandardMove> {
+        // First collect every move that fits the movement pattern of the pieces, without caring about checks or
+        // special rules. The validator then filters out everything that is not actually legal.
+        let validator = StandardMoveValidator {};
+        let board = position.board();
+        let mut candidates = Vec::new();
+        for (from, piece) in board.iter().filter(|(_, piece)| piece.color() == position.side_to_move()) {
+            piece_candidates(board, piece, from, &mut candidates);
+        }
+        // A castling move where the king moves onto its rook can already be among the king steps.
+        for side in [CastlingSide::KingSide, CastlingSide::QueenSide] {
+            if let Some(castling) = position.castling_move(position.side_to_move(), side) && !candidates.contains(&castling) {
+                candidates.push(castling);
+            }
+        }
+
+        candidates.retain(|mov| validator.validate(position, mov));
+        candidates
+    }
+}
+
+// Returns the square at the given offset from a square, or `None` if it is outside the board.
+fn offset<B: Board>(board: &B, square: Square, rows: i32, columns: i32) -> Option<Square> {
+    square.offset(rows, columns)
+        .filter(|&target| board.valid_square(target))
+}
+
+// Adds all moves that fit the movement pattern of a piece, ignoring everything else on the board.
+fn piece_candidates<B: Board<Token = ColoredStandardPiece>>(board: &B, piece: &ColoredStandardPiece, from: Square, moves: &mut Vec<StandardMove>) {
+    match piece.piece() {
+        StandardPiece::Pawn => pawn_candidates(board, piece.color(), from, moves),
+        StandardPiece::Knight => step_candidates(board, from, &KNIGHT_OFFSETS, moves),
+        StandardPiece::Bishop => slide_candidates(board, from, &BISHOP_DIRECTIONS, moves),
+        StandardPiece::Rook => slide_candidates(board, from, &ROOK_DIRECTIONS, moves),
+        StandardPiece::Queen => {
+            slide_candidates(board, from, &BISHOP_DIRECTIONS, moves);
+            slide_candidates(board, from, &ROOK_DIRECTIONS, moves);
+        }
+        StandardPiece::King => {
+            step_candidates(board, from, &KING_OFFSETS, moves);
+        }
+    }
+}
+
+fn pawn_candidates<B: Board<Token = ColoredStandardPiece>>(board: &B, color: PieceColor, from: Square, moves: &mut Vec<StandardMove>) {
+    let forward = if color == PieceColor::White { 1 } else { -1 };
+    let last_row = if color == PieceColor::White { board.height() - 1 } else { 0 };
+    let targets = [
+        offset(board, from, forward, 0),
+        offset(board, from, 2 * forward, 0),
+        offset(board, from, forward, -1),
+        offset(board, from, forward, 1),
+    ];
+
+    for to in targets.into_iter().flatten() {
+        // Reaching the last row generates one move for every piece the pawn can promote to.
+        if to.row == last_row {
+            moves.extend(PROMOTIONS.iter().map(|&promotion| StandardMove::promote(from, to, promotion)));
+        } else {
+            moves.push(StandardMove::new(from, to));
+        }
+    }
+}
+
+fn step_candidates<B: Board>(board: &B, from: Square, offsets: &[(i32, i32)], moves: &mut Vec<StandardMove>) {
+    for &(rows, columns) in offsets {
+        if let Some(to) = offset(board, from, rows, columns) {
+            moves.push(StandardMove::new(from, to));
+        }
+    }
+}
+
+fn slide_candidates<B: Board>(board: &B, from: Square, directions: &[(i32, i32)], moves: &mut Vec<StandardMove>) {
+    for &(rows, columns) in directions {
+        let mut current = from;
+        // Keep sliding until we leave the board or hit a piece. The square with the piece is still a candidate,
+        // since it might be a capture.
+        while let Some(to) = offset(board, current, rows, columns) {
+            moves.push(StandardMove::new(from, to));
+            if board.get(to).is_some() {
+                break;
+            }
+            current = to;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::mailbox::MailboxBoard;
+    use super::*;
+
+    fn play(position: &mut StandardPosition<MailboxBoard<ColoredStandardPiece>>, from: &str, to: &str) {
+        let mov = StandardMove::new(Square::parse(from).unwrap(), Square::parse(to).unwrap());
+        position.make_move(&mov).expect("Move should be applied");
+    }
+
+    fn generates(moves: &[StandardMove], from: &str, to: &str) -> bool {
+        moves.contains(&StandardMove::new(Square::parse(from).unwrap(), Square::parse(to).unwrap()))
+    }
+
+    #[test]
+    fn starting_position() {
+        let generator = StandardMoveGenerator {};
+        let mut position = StandardPosition::starting_position();
+        let moves = generator.generate(&position);
+        assert_eq!(moves.len(), 20);
+        assert!(generates(&moves, "e2", "e4"));
+        assert!(generates(&moves, "g1", "f3"));
+        assert!(!generates(&moves, "e7", "e5"));
+
+        play(&mut position, "e2", "e4");
+        let moves = generator.generate(&position);
+        assert_eq!(moves.len(), 20);
+        assert!(generates(&moves, "e7", "e5"));
+    }
+
+    #[test]
+    fn generated_moves_are_valid() {
+        let generator = StandardMoveGenerator {};
+        let validator = StandardMoveValidator {};
+        let mut position = StandardPosition::starting_position();
+        for (from, to) in [("e2", "e4"), ("d7", "d5"), ("e4", "d5"), ("d8", "d5"), ("b1", "c3")] {
+            play(&mut position, from, to);
+        }
+
+        let moves = generator.generate(&position);
+        assert!(!moves.is_empty());
+        assert!(moves.iter().all(|mov| validator.validate(&position, mov)));
+    }
+
+    #[test]
+    fn special_moves() {
+        let generator = StandardMoveGenerator {};
+        let mut position = StandardPosition::new(MailboxBoard::new(8, 8));
+        let board = position.board_mut();
+        board.set(Square::parse("e1").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::King, PieceColor::White))).unwrap();
+        board.set(Square::parse("h1").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Rook, PieceColor::White))).unwrap();
+        board.set(Square::parse("a1").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Rook, PieceColor::White))).unwrap();
+        board.set(Square::parse("c7").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::White))).unwrap();
+        board.set(Square::parse("e5").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::White))).unwrap();
+        board.set(Square::parse("d7").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::Black))).unwrap();
+        board.set(Square::parse("h8").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::King, PieceColor::Black))).unwrap();
+        let mut rights = position.castling_rights();
+        rights.set(PieceColor::White, CastlingSide::KingSide, Some(7));
+        rights.set(PieceColor::White, CastlingSide::QueenSide, Some(0));
+        position.set_castling_rights(rights);
+        position.set_side_to_move(PieceColor::Black);
+        play(&mut position, "d7", "d5");
+
+        let moves = generator.generate(&position);
+        // Both castling moves
+        assert!(generates(&moves, "e1", "g1"));
+        assert!(generates(&moves, "e1", "c1"));
+        // En passant
+        assert!(generates(&moves, "e5", "d6"));
+        // All four promotions
+        let c7 = Square::parse("c7").unwrap();
+        let c8 = Square::parse("c8").unwrap();
+        for piece in PROMOTIONS {
+            assert!(moves.contains(&StandardMove::promote(c7, c8, piece)));
+        }
+        assert!(!generates(&moves, "c7", "c8"));
+    }
+
+    #[test]
+    fn checkmate_has_no_moves() {
+        // Fool's mate
+        let generator = StandardMoveGenerator {};
+        let mut position = StandardPosition::starting_position();
+        for (from, to) in [("f2", "f3"), ("e7", "e5"), ("g2", "g4"), ("d8", "h4")] {
+            play(&mut position, from, to);
+        }
+        assert!(generator.generate(&position).is_empty());
+    }
+}
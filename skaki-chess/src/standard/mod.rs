@@ -1,3 +1,14 @@
 pub mod moves;
 pub mod validator;
-pub mod piece;
\ No newline at end of file
+pub mod generator;
+pub mod piece;
+pub mod position;
+pub mod perft;
+pub mod result;
+pub mod draw;
+pub mod game;
+pub mod fen;
+pub mod san;
+pub mod uci;
+pub mod pgn;
+pub mod zobrist;
\ No newline at end of file
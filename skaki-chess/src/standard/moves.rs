@@ -1,6 +1,10 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use anyhow::{bail, ensure, Context};
 use crate::moves::Move;
 use crate::square::Square;
 use crate::standard::piece::StandardPiece;
+use crate::standard::position::CastlingSide;
 
 /// A standard move in a game of chess starts at a square and ends at a square.
 /// Note that there is no information required about which piece made the move, since that can be inferred from the board state, assuming
@@ -31,6 +35,20 @@ impl StandardMove {
         }
     }
 
+    /// Create a castling move for a king that starts on its regular square, such as e1 on an 8x8 board. Castling is
+    /// then represented as the king moving two squares toward the rook it castles with, the rook movement is implied.
+    /// For kings on other squares, such as in Chess960, use `StandardPosition::castling_move` instead.
+    ///
+    /// Returns `None` if the king is too close to the edge to move two squares. Like `Square::offset`, this knows
+    /// nothing about the size of the board, so the destination may still be outside of it.
+    pub fn castle(king: Square, side: CastlingSide) -> Option<Self> {
+        let columns = match side {
+            CastlingSide::KingSide => 2,
+            CastlingSide::QueenSide => -2,
+        };
+        king.offset(0, columns).map(|to| Self::new(king, to))
+    }
+
     pub fn from(&self) -> Square {
         self.from
     }
@@ -38,6 +56,92 @@ impl StandardMove {
     pub fn to(&self) -> Square {
         self.to
     }
+
+    /// The piece a pawn promotes to with this move, if any.
+    pub fn promotion(&self) -> Option<StandardPiece> {
+        self.promotion
+    }
+}
+
+impl Move for StandardMove {}
+
+// Writes the move in the long algebraic notation used by UCI, e.g. `e2e4` or `e7e8q` for a promotion.
+// Castling is written as the move of the king, like `e1g1`, or as the king moving onto its rook when the king moves
+// less than two squares, which can happen in Chess960.
+impl Display for StandardMove {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.from, self.to)?;
+        if let Some(promotion) = self.promotion {
+            write!(f, "{}", promotion.letter().to_ascii_lowercase())?;
+        }
+        Ok(())
+    }
 }
 
-impl Move for StandardMove {}
\ No newline at end of file
+// Parses a move in UCI notation. This is strict: both squares must be written in lowercase without leading zeroes,
+// and the only thing allowed after them is a lowercase promotion piece.
+impl FromStr for StandardMove {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ensure!(s.is_ascii(), "UCI move '{s}' contains characters that are not ascii");
+        let (from, rest) = split_square(s).with_context(|| format!("Invalid starting square in UCI move '{s}'"))?;
+        let (to, rest) = split_square(rest).with_context(|| format!("Invalid destination square in UCI move '{s}'"))?;
+
+        let promotion = match rest {
+            "" => None,
+            "n" => Some(StandardPiece::Knight),
+            "b" => Some(StandardPiece::Bishop),
+            "r" => Some(StandardPiece::Rook),
+            "q" => Some(StandardPiece::Queen),
+            _ => bail!("Invalid promotion '{rest}' in UCI move '{s}', expected one of 'nbrq'"),
+        };
+        ensure!(from != to, "UCI move '{s}' starts and ends on the same square");
+
+        Ok(Self { from, to, promotion })
+    }
+}
+
+// Reads a single square from the start of a UCI move, and returns the rest of the string.
+fn split_square(s: &str) -> anyhow::Result<(Square, &str)> {
+    let letters = s.len() - s.trim_start_matches(|c: char| c.is_ascii_lowercase()).len();
+    ensure!(letters > 0, "Square must start with a lowercase file letter");
+    let rest = &s[letters..];
+    let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    ensure!(digits > 0, "Square must have a rank number");
+    ensure!(!rest.starts_with('0'), "Rank number cannot start with 0");
+    let square = Square::parse(&s[..letters + digits])?;
+    Ok((square, &s[letters + digits..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uci_round_trip() {
+        for uci in ["e2e4", "g1f3", "e7e8q", "a2a1n", "b7c8r", "h7h8b", "a9a10", "j10j9", "aa10ab11", "z1aa1"] {
+            let mov: StandardMove = uci.parse().unwrap();
+            assert_eq!(mov.to_string(), uci);
+        }
+
+        let mov: StandardMove = "e7e8q".parse().unwrap();
+        assert_eq!(mov, StandardMove::promote(Square::parse("e7").unwrap(), Square::parse("e8").unwrap(), StandardPiece::Queen));
+    }
+
+    #[test]
+    fn strict_uci_parsing() {
+        for uci in ["", "e2", "e2e", "E2e4", "e2E4", "e2e4Q", "e7e8k", "e7e8p", "e7e8qq", "e0e4", "e02e04", "e2 e4", "e2-e4", "e2e2", "exd5"] {
+            assert!(uci.parse::<StandardMove>().is_err(), "{uci} should not parse");
+        }
+    }
+
+    #[test]
+    fn castle_near_edge() {
+        let e1 = Square::parse("e1").unwrap();
+        assert_eq!(StandardMove::castle(e1, CastlingSide::KingSide), Some(StandardMove::new(e1, Square::parse("g1").unwrap())));
+        assert_eq!(StandardMove::castle(e1, CastlingSide::QueenSide), Some(StandardMove::new(e1, Square::parse("c1").unwrap())));
+        assert_eq!(StandardMove::castle(Square::parse("b1").unwrap(), CastlingSide::QueenSide), None);
+        assert_eq!(StandardMove::castle(Square::parse("a1").unwrap(), CastlingSide::QueenSide), None);
+    }
+}
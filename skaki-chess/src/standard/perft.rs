@@ -0,0 +1,209 @@
+use crate::board::Board;
+use crate::generator::MoveGenerator;
+use crate::standard::generator::StandardMoveGenerator;
+use crate::standard::moves::StandardMove;
+use crate::standard::piece::ColoredStandardPiece;
+use crate::standard::position::StandardPosition;
+
+/// Counts the number of leaf nodes in the tree of legal moves of the given depth, starting at this position.
+/// Comparing these numbers against known values is the standard way to verify the correctness of move generation.
+pub fn perft<B: Board<Token = ColoredStandardPiece> + Clone>(position: &StandardPosition<B>, depth: u32) -> u64 {
+    count(&mut position.clone(), depth)
+}
+
+/// Like `perft`, but splits up the node count per legal move in the root position.
+/// This makes it easy to narrow down which move causes a difference with a reference move generator.
+pub fn divide<B: Board<Token = ColoredStandardPiece> + Clone>(position: &StandardPosition<B>, depth: u32) -> Vec<(StandardMove, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+
+    let mut position = position.clone();
+    let generator = StandardMoveGenerator {};
+    generator.generate(&position)
+        .into_iter()
+        .map(|mov| {
+            let undo = position.make(&mov).expect("Generated moves are always playable");
+            let nodes = count(&mut position, depth - 1);
+            position.unmake(undo).expect("Undo record belongs to this position");
+            (mov, nodes)
+        })
+        .collect()
+}
+
+// Counts the leaf nodes by playing and taking back moves on a single position.
+fn count<B: Board<Token = ColoredStandardPiece> + Clone>(position: &mut StandardPosition<B>, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let generator = StandardMoveGenerator {};
+    let moves = generator.generate(position);
+    // At the last level, every legal move is exactly one leaf, so there is no need to play them.
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    moves.iter()
+        .map(|mov| {
+            let undo = position.make(mov).expect("Generated moves are always playable");
+            let nodes = count(position, depth - 1);
+            position.unmake(undo).expect("Undo record belongs to this position");
+            nodes
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::mailbox::MailboxBoard;
+    use crate::square::Square;
+    use crate::standard::position::CastlingSide;
+    use super::*;
+
+    // Node counts of the reference positions from the chess programming wiki, see
+    // https://www.chessprogramming.org/Perft_Results
+    const START: [u64; 5] = [20, 400, 8902, 197281, 4865609];
+    const KIWIPETE: [u64; 4] = [48, 2039, 97862, 4085603];
+    const POSITION_3: [u64; 5] = [14, 191, 2812, 43238, 674624];
+    const POSITION_4: [u64; 4] = [6, 264, 9467, 422333];
+    const POSITION_5: [u64; 4] = [44, 1486, 62379, 2103487];
+    const POSITION_6: [u64; 4] = [46, 2079, 89890, 3894594];
+    // The first Chess960 position from https://www.chessprogramming.org/Chess960_Perft_Results
+    const CHESS960_1: [u64; 5] = [21, 528, 12189, 326672, 8146062];
+
+    fn kiwipete() -> StandardPosition<MailboxBoard<ColoredStandardPiece>> {
+        StandardPosition::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq -").unwrap()
+    }
+
+    fn position_3() -> StandardPosition<MailboxBoard<ColoredStandardPiece>> {
+        StandardPosition::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - -").unwrap()
+    }
+
+    fn position_4() -> StandardPosition<MailboxBoard<ColoredStandardPiece>> {
+        StandardPosition::from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq -").unwrap()
+    }
+
+    fn position_5() -> StandardPosition<MailboxBoard<ColoredStandardPiece>> {
+        StandardPosition::from_fen("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ -").unwrap()
+    }
+
+    fn position_6() -> StandardPosition<MailboxBoard<ColoredStandardPiece>> {
+        StandardPosition::from_fen("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - -").unwrap()
+    }
+
+    fn chess960_1() -> StandardPosition<MailboxBoard<ColoredStandardPiece>> {
+        StandardPosition::from_fen("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9").unwrap()
+    }
+
+    // Checks the node counts of a position for every depth up to and including `depth`.
+    fn assert_perft(position: &StandardPosition<MailboxBoard<ColoredStandardPiece>>, expected: &[u64], depth: usize) {
+        for (index, &nodes) in expected.iter().take(depth).enumerate() {
+            assert_eq!(perft(position, index as u32 + 1), nodes, "Wrong node count at depth {}", index + 1);
+        }
+    }
+
+    #[test]
+    fn depth_zero() {
+        let position = StandardPosition::starting_position();
+        assert_eq!(perft(&position, 0), 1);
+        assert!(divide(&position, 0).is_empty());
+    }
+
+    #[test]
+    fn divide_sums_to_perft() {
+        let position = kiwipete();
+        let divided = divide(&position, 2);
+        assert_eq!(divided.len() as u64, KIWIPETE[0]);
+        assert_eq!(divided.iter().map(|(_, nodes)| nodes).sum::<u64>(), KIWIPETE[1]);
+        // Castling on both sides is available in the root position.
+        let e1 = Square::parse("e1").unwrap();
+        assert!(divided.iter().any(|(mov, _)| *mov == StandardMove::castle(e1, CastlingSide::KingSide).unwrap()));
+        assert!(divided.iter().any(|(mov, _)| *mov == StandardMove::castle(e1, CastlingSide::QueenSide).unwrap()));
+    }
+
+    #[test]
+    fn en_passant_root() {
+        // After 1. e4, black has no en passant capture, but the square is set. This should not change anything.
+        let position = StandardPosition::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3").unwrap();
+        assert_eq!(perft(&position, 1), 20);
+        assert_eq!(perft(&position, 2), 600);
+    }
+
+    #[test]
+    fn start_position() {
+        assert_perft(&StandardPosition::starting_position(), &START, 3);
+    }
+
+    #[test]
+    fn kiwipete_shallow() {
+        assert_perft(&kiwipete(), &KIWIPETE, 3);
+    }
+
+    #[test]
+    fn position_3_shallow() {
+        assert_perft(&position_3(), &POSITION_3, 4);
+    }
+
+    #[test]
+    fn position_4_shallow() {
+        assert_perft(&position_4(), &POSITION_4, 3);
+    }
+
+    #[test]
+    fn position_5_shallow() {
+        assert_perft(&position_5(), &POSITION_5, 3);
+    }
+
+    #[test]
+    fn position_6_shallow() {
+        assert_perft(&position_6(), &POSITION_6, 3);
+    }
+
+    #[test]
+    fn chess960_shallow() {
+        assert_perft(&chess960_1(), &CHESS960_1, 3);
+    }
+
+    #[test]
+    #[ignore = "slow, run with --ignored"]
+    fn start_position_deep() {
+        assert_perft(&StandardPosition::starting_position(), &START, START.len());
+    }
+
+    #[test]
+    #[ignore = "slow, run with --ignored"]
+    fn kiwipete_deep() {
+        assert_perft(&kiwipete(), &KIWIPETE, KIWIPETE.len());
+    }
+
+    #[test]
+    #[ignore = "slow, run with --ignored"]
+    fn position_3_deep() {
+        assert_perft(&position_3(), &POSITION_3, POSITION_3.len());
+    }
+
+    #[test]
+    #[ignore = "slow, run with --ignored"]
+    fn position_4_deep() {
+        assert_perft(&position_4(), &POSITION_4, POSITION_4.len());
+    }
+
+    #[test]
+    #[ignore = "slow, run with --ignored"]
+    fn position_5_deep() {
+        assert_perft(&position_5(), &POSITION_5, POSITION_5.len());
+    }
+
+    #[test]
+    #[ignore = "slow, run with --ignored"]
+    fn position_6_deep() {
+        assert_perft(&position_6(), &POSITION_6, POSITION_6.len());
+    }
+
+    #[test]
+    #[ignore = "slow, run with --ignored"]
+    fn chess960_deep() {
+        assert_perft(&chess960_1(), &CHESS960_1, CHESS960_1.len());
+    }
+}
@@ -0,0 +1,719 @@
+use std::iter::Peekable;
+use std::str::Chars;
+use anyhow::{bail, ensure, Context};
+use crate::board::mailbox::MailboxBoard;
+use crate::standard::fen::STARTING_FEN;
+use crate::standard::game::StandardGame;
+use crate::standard::moves::StandardMove;
+use crate::standard::piece::{ColoredStandardPiece, PieceColor};
+use crate::standard::position::StandardPosition;
+use crate::standard::result::GameResult;
+use crate::standard::validator::StandardMoveValidator;
+use crate::validator::MoveValidator;
+
+/// The tags every PGN game has, in the order they are exported.
+pub const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+// Lines of movetext in export format are wrapped to stay within this many characters.
+const LINE_LENGTH: usize = 79;
+
+/// A move in a PGN game, together with its annotations and the alternatives to it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PgnMove {
+    mov: StandardMove,
+    nags: Vec<u8>,
+    comment: Option<String>,
+    variations: Vec<Vec<PgnMove>>,
+}
+
+impl PgnMove {
+    /// A move without any annotations.
+    pub fn new(mov: StandardMove) -> Self {
+        Self {
+            mov,
+            nags: Vec::new(),
+            comment: None,
+            variations: Vec::new(),
+        }
+    }
+
+    pub fn mov(&self) -> StandardMove {
+        self.mov
+    }
+
+    /// The numeric annotation glyphs of this move. Suffixes like `!?` are stored as their equivalent glyph.
+    pub fn nags(&self) -> &[u8] {
+        &self.nags
+    }
+
+    /// The comment after this move.
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+
+    /// Alternative lines that could have been played instead of this move. Each line starts in the same position
+    /// as this move.
+    pub fn variations(&self) -> &[Vec<PgnMove>] {
+        &self.variations
+    }
+
+    fn add_comment(&mut self, comment: String) {
+        self.comment = Some(match self.comment.take() {
+            Some(existing) => format!("{existing} {comment}"),
+            None => comment,
+        });
+    }
+}
+
+/// A single game in Portable Game Notation.
+#[derive(Debug, Clone)]
+pub struct PgnGame {
+    tags: Vec<(String, String)>,
+    start: StandardPosition<MailboxBoard<ColoredStandardPiece>>,
+    comment: Option<String>,
+    moves: Vec<PgnMove>,
+    // The position after the last move of the main line, so adding a move does not need to replay the game.
+    end: StandardPosition<MailboxBoard<ColoredStandardPiece>>,
+}
+
+impl PgnGame {
+    /// Creates a game without moves starting in the given position. The tags of the seven tag roster are set to
+    /// their unknown values.
+    pub fn new(start: StandardPosition<MailboxBoard<ColoredStandardPiece>>) -> Self {
+        let tags = SEVEN_TAG_ROSTER.iter()
+            .map(|&name| {
+                let value = match name {
+                    "Date" => "????.??.??",
+                    "Result" => "*",
+                    _ => "?",
+                };
+                (name.to_string(), value.to_string())
+            })
+            .collect();
+
+        Self {
+            tags,
+            end: start.clone(),
+            start,
+            comment: None,
+            moves: Vec::new(),
+        }
+    }
+
+    /// All tags of the game, in the order they were read or set.
+    pub fn tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+
+    /// The value of a tag, or `None` if the game does not have this tag.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str())
+    }
+
+    /// Sets the value of a tag, adding it if the game does not have it yet.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, existing)) => *existing = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// The result of the game as written in PGN: `1-0`, `0-1`, `1/2-1/2`, or `*` if the game is still going.
+    pub fn result(&self) -> &str {
+        self.tag("Result").unwrap_or("*")
+    }
+
+    /// Sets the `Result` tag from the result of a finished game.
+    pub fn set_result(&mut self, result: Option<GameResult>) {
+        let result = match result.map(|result| result.winner()) {
+            None => "*",
+            Some(None) => "1/2-1/2",
+            Some(Some(PieceColor::White)) => "1-0",
+            Some(Some(PieceColor::Black)) => "0-1",
+        };
+        self.set_tag("Result", result);
+    }
+
+    /// The position the game starts in. This is the regular starting position unless the game has a `FEN` tag.
+    pub fn start(&self) -> &StandardPosition<MailboxBoard<ColoredStandardPiece>> {
+        &self.start
+    }
+
+    /// The comment before the first move of the game.
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+
+    /// The moves of the main line of the game.
+    pub fn moves(&self) -> &[PgnMove] {
+        &self.moves
+    }
+
+    /// Adds a move to the end of the main line.
+    ///
+    /// Returns Err(_) if the move is not legal.
+    pub fn push_move(&mut self, mov: StandardMove) -> anyhow::Result<()> {
+        let validator = StandardMoveValidator {};
+        ensure!(validator.validate(&self.end, &mov), "Move is not legal in the current position");
+        self.end.make_move(&mov)?;
+        self.moves.push(PgnMove::new(mov));
+        Ok(())
+    }
+
+    /// Replays the main line of the game.
+    pub fn to_game(&self) -> anyhow::Result<StandardGame<MailboxBoard<ColoredStandardPiece>>> {
+        let mut game = StandardGame::new(self.start.clone());
+        for (ply, mov) in self.moves.iter().enumerate() {
+            game.make_move(&mov.mov).with_context(|| format!("Cannot replay ply {}", ply + 1))?;
+        }
+        Ok(game)
+    }
+
+    /// Writes the game in PGN export format. The seven tag roster comes first, followed by the other tags in
+    /// alphabetical order, and the movetext is wrapped to lines of at most 79 characters.
+    pub fn to_pgn(&self) -> String {
+        let mut pgn = String::new();
+        let mut tags: Vec<(String, String)> = SEVEN_TAG_ROSTER.iter()
+            .map(|&name| (name.to_string(), self.tag(name).unwrap_or(if name == "Result" { "*" } else { "?" }).to_string()))
+            .collect();
+        let mut other: Vec<(String, String)> = self.tags.iter()
+            .filter(|(name, _)| !SEVEN_TAG_ROSTER.contains(&name.as_str()))
+            .cloned()
+            .collect();
+        // Games that do not start in the regular starting position need to say where they start.
+        let fen = self.start.to_fen();
+        if fen != STARTING_FEN && self.tag("FEN").is_none() {
+            other.push(("SetUp".to_string(), "1".to_string()));
+            other.push(("FEN".to_string(), fen));
+        }
+        other.sort();
+        tags.extend(other);
+        for (name, value) in tags {
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, value.replace('\\', "\\\\").replace('"', "\\\"")));
+        }
+        pgn.push('\n');
+
+        let mut tokens = Vec::new();
+        if let Some(comment) = &self.comment {
+            push_comment(&mut tokens, comment);
+        }
+        write_line(&mut tokens, &self.start, &self.moves);
+        tokens.push(self.result().to_string());
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > LINE_LENGTH {
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        pgn.push_str(&line);
+        pgn.push_str("\n\n");
+        pgn
+    }
+}
+
+/// Writes a list of games in PGN export format, separated by empty lines.
+pub fn write_pgn(games: &[PgnGame]) -> String {
+    games.iter().map(PgnGame::to_pgn).collect()
+}
+
+// Adds the tokens of a line of moves starting in the given position, including annotations and variations.
+fn write_line(tokens: &mut Vec<String>, start: &StandardPosition<MailboxBoard<ColoredStandardPiece>>, moves: &[PgnMove]) {
+    let mut position = start.clone();
+    // Black moves need a move number if they are the first in a line, or follow a comment or variation.
+    let mut needs_number = true;
+    for mov in moves {
+        let number = position.fullmove_number();
+        match position.side_to_move() {
+            PieceColor::White => tokens.push(format!("{number}.")),
+            PieceColor::Black if needs_number => tokens.push(format!("{number}...")),
+            _ => {}
+        }
+
+        tokens.push(position.to_san(&mov.mov).expect("Moves are validated when they are added"));
+        tokens.extend(mov.nags.iter().map(|nag| format!("${nag}")));
+        if let Some(comment) = &mov.comment {
+            push_comment(tokens, comment);
+        }
+        for variation in &mov.variations {
+            // Parentheses are written right next to the moves they enclose.
+            let first = tokens.len();
+            write_line(tokens, &position, variation);
+            if tokens.len() == first {
+                tokens.push("()".to_string());
+            } else {
+                tokens[first].insert(0, '(');
+                tokens.last_mut().expect("Not empty").push(')');
+            }
+        }
+        needs_number = mov.comment.is_some() || !mov.variations.is_empty();
+
+        position.make_move(&mov.mov).expect("Moves are validated when they are added");
+    }
+}
+
+// Adds a comment as separate words, so it can be wrapped over multiple lines.
+fn push_comment(tokens: &mut Vec<String>, comment: &str) {
+    let words: Vec<&str> = comment.split_whitespace().collect();
+    if words.is_empty() {
+        tokens.push("{}".to_string());
+        return;
+    }
+
+    let last = words.len() - 1;
+    for (index, word) in words.into_iter().enumerate() {
+        let mut token = word.to_string();
+        if index == 0 {
+            token.insert(0, '{');
+        }
+        if index == last {
+            token.push('}');
+        }
+        tokens.push(token);
+    }
+}
+
+/// Reads all games from a PGN file. Moves are replayed through the standard validator, so every game that is read
+/// only contains legal moves. Comments, annotation glyphs and (nested) variations are kept. When the `Result` tag
+/// and the termination marker of a game disagree, the termination marker wins.
+///
+/// Returns Err(_) if the file is malformed or contains an illegal move. The error contains the number of the game
+/// (starting from 1) and the ply within the game where reading failed.
+pub fn read_pgn(pgn: &str) -> anyhow::Result<Vec<PgnGame>> {
+    let tokens = tokenize(pgn)?;
+    let mut tokens = tokens.into_iter().peekable();
+    let mut games = Vec::new();
+    while tokens.peek().is_some() {
+        let index = games.len() + 1;
+        let game = read_game(&mut tokens).with_context(|| format!("Invalid PGN in game {index}"))?;
+        games.push(game);
+    }
+
+    Ok(games)
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    Open,
+    Close,
+    Termination(String),
+    San(String),
+}
+
+type Tokens = Peekable<std::vec::IntoIter<Token>>;
+
+fn read_game(tokens: &mut Tokens) -> anyhow::Result<PgnGame> {
+    let mut tags = Vec::new();
+    while let Some(Token::Tag(_, _)) = tokens.peek() {
+        let Some(Token::Tag(name, value)) = tokens.next() else { unreachable!() };
+        tags.push((name, value));
+    }
+
+    // Games that do not start in the regular starting position have their position in a FEN tag.
+    let fen = tags.iter().find(|(name, _)| name == "FEN").map(|(_, value)| value.as_str());
+    let start = StandardPosition::from_fen(fen.unwrap_or(STARTING_FEN)).context("Invalid FEN tag")?;
+    let mut game = PgnGame::new(start);
+    for (name, value) in tags {
+        game.set_tag(&name, &value);
+    }
+
+    let mut comment = None;
+    let (moves, end) = read_line(tokens, game.start(), 1, &mut comment, false)?;
+    game.comment = comment;
+    game.moves = moves;
+    game.end = end;
+
+    // A game without termination marker ends at the end of the file, or where the tags of the next game start.
+    // Those tags are left for the next game.
+    if let Some(Token::Termination(result)) = tokens.next_if(|token| matches!(token, Token::Termination(_))) {
+        // The termination marker is the authority on the result, so it replaces a Result tag that disagrees.
+        game.set_tag("Result", &result);
+    }
+
+    Ok(game)
+}
+
+// Reads moves until the end of the line. Variations end at a closing parenthesis, the main line ends at a
+// termination marker or the next tag. The closing parenthesis is consumed, other end markers are left alone.
+// `first_ply` is the number of the first move in the game, and is only used for error messages.
+// Returns the moves together with the position after the last of them.
+fn read_line(tokens: &mut Tokens, start: &StandardPosition<MailboxBoard<ColoredStandardPiece>>, first_ply: usize,
+             comment: &mut Option<String>, is_variation: bool)
+             -> anyhow::Result<(Vec<PgnMove>, StandardPosition<MailboxBoard<ColoredStandardPiece>>)> {
+    let mut moves: Vec<PgnMove> = Vec::new();
+    let mut position = start.clone();
+    // The position before the last move, which is where variations on that move start.
+    let mut previous = start.clone();
+
+    loop {
+        let ply = first_ply + moves.len();
+        match tokens.peek() {
+            None | Some(Token::Tag(_, _)) | Some(Token::Termination(_)) => {
+                ensure!(!is_variation, "Variation before ply {ply} is not closed");
+                return Ok((moves, position));
+            }
+            Some(Token::Close) => {
+                ensure!(is_variation, "Unexpected ')' at ply {ply}");
+                tokens.next();
+                return Ok((moves, position));
+            }
+            _ => {}
+        }
+
+        match tokens.next().expect("Already peeked") {
+            Token::San(san) => {
+                let mov = position.parse_san(&san).with_context(|| format!("Invalid move '{san}' at ply {ply}"))?;
+                previous = position.clone();
+                position.make_move(&mov).with_context(|| format!("Cannot play move '{san}' at ply {ply}"))?;
+                moves.push(PgnMove::new(mov));
+            }
+            Token::Comment(text) => match moves.last_mut() {
+                Some(last) => last.add_comment(text),
+                None => *comment = Some(match comment.take() {
+                    Some(existing) => format!("{existing} {text}"),
+                    None => text,
+                }),
+            },
+            Token::Nag(nag) => {
+                let last = moves.last_mut().with_context(|| format!("Annotation glyph before the first move at ply {ply}"))?;
+                last.nags.push(nag);
+            }
+            Token::Open => {
+                let variation_ply = ply - 1;
+                let last = moves.last_mut().with_context(|| format!("Variation before the first move at ply {ply}"))?;
+                // A comment at the start of a variation has no move to belong to, so it is kept with the
+                // move the variation replaces.
+                let mut leading = None;
+                let (variation, _) = read_line(tokens, &previous, variation_ply, &mut leading, true)?;
+                if let Some(leading) = leading {
+                    last.add_comment(leading);
+                }
+                last.variations.push(variation);
+            }
+            token => bail!("Unexpected {token:?} at ply {ply}"),
+        }
+    }
+}
+
+fn tokenize(pgn: &str) -> anyhow::Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = pgn.chars().peekable();
+    let mut line = 1;
+    let mut at_line_start = true;
+    // Games are only split up after tokenizing, so for error messages the game is counted by its termination marker.
+    let mut game = 1;
+    while let Some(&c) = chars.peek() {
+        let (current_game, current_line) = (game, line);
+        let location = move || format!("Invalid PGN in game {current_game} on line {current_line}");
+
+        if c == '\n' {
+            line += 1;
+            at_line_start = true;
+            chars.next();
+            continue;
+        }
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let start_of_line = at_line_start;
+        at_line_start = false;
+        match c {
+            // A percent sign at the start of a line escapes the rest of the line.
+            '%' if start_of_line => skip_line(&mut chars),
+            ';' => skip_line(&mut chars),
+            '[' => {
+                chars.next();
+                let tag = read_tag(&mut chars, &mut line).with_context(location)?;
+                tokens.push(tag);
+            }
+            '{' => {
+                chars.next();
+                let mut comment = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            comment.push(c);
+                        }
+                        None => return Err(anyhow::anyhow!("Comment is not closed")).with_context(location),
+                    }
+                }
+                tokens.push(Token::Comment(comment.trim().to_string()));
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '$' => {
+                chars.next();
+                let digits = take_while(&mut chars, |c| c.is_ascii_digit());
+                let nag = digits.parse().with_context(|| format!("Invalid annotation glyph '${digits}'")).with_context(location)?;
+                tokens.push(Token::Nag(nag));
+            }
+            '!' | '?' => {
+                let suffix = take_while(&mut chars, |c| c == '!' || c == '?');
+                let nag = match suffix.as_str() {
+                    "!" => 1,
+                    "?" => 2,
+                    "!!" => 3,
+                    "??" => 4,
+                    "!?" => 5,
+                    "?!" => 6,
+                    _ => return Err(anyhow::anyhow!("Invalid move suffix '{suffix}'")).with_context(location),
+                };
+                tokens.push(Token::Nag(nag));
+            }
+            '*' => {
+                chars.next();
+                game += 1;
+                tokens.push(Token::Termination("*".to_string()));
+            }
+            _ => {
+                let symbol = take_while(&mut chars, |c| c.is_ascii_alphanumeric() || "_+#=:-/.".contains(c));
+                ensure!(!symbol.is_empty(), "{}: Unexpected character '{c}'", location());
+                if let Some(token) = symbol_token(&symbol) {
+                    if matches!(token, Token::Termination(_)) {
+                        game += 1;
+                    }
+                    tokens.push(token);
+                }
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+// Turns a symbol into a token. Move numbers are not needed to read the moves, so they return `None`.
+fn symbol_token(symbol: &str) -> Option<Token> {
+    if matches!(symbol, "1-0" | "0-1" | "1/2-1/2") {
+        return Some(Token::Termination(symbol.to_string()));
+    }
+
+    // Move numbers are digits followed by periods, and are sometimes written right against the move (`1.e4`).
+    let without_number = symbol.trim_start_matches(|c: char| c.is_ascii_digit());
+    let rest = if without_number.starts_with('.') || without_number.is_empty() {
+        without_number.trim_start_matches('.')
+    } else {
+        symbol
+    };
+    (!rest.is_empty()).then(|| Token::San(rest.to_string()))
+}
+
+fn read_tag(chars: &mut Peekable<Chars>, line: &mut usize) -> anyhow::Result<Token> {
+    skip_spaces(chars, line);
+    let name = take_while(chars, |c| c.is_ascii_alphanumeric() || c == '_');
+    ensure!(!name.is_empty(), "Tag has no name");
+    skip_spaces(chars, line);
+    ensure!(chars.next() == Some('"'), "Value of tag '{name}' must be a string");
+
+    let mut value = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => break,
+            Some('\\') => match chars.next() {
+                Some(c @ ('"' | '\\')) => value.push(c),
+                _ => bail!("Invalid escape sequence in value of tag '{name}'"),
+            },
+            Some('\n') | None => bail!("Value of tag '{name}' is not closed"),
+            Some(c) => value.push(c),
+        }
+    }
+
+    skip_spaces(chars, line);
+    ensure!(chars.next() == Some(']'), "Tag '{name}' is not closed");
+    Ok(Token::Tag(name, value))
+}
+
+fn take_while(chars: &mut Peekable<Chars>, predicate: impl Fn(char) -> bool) -> String {
+    let mut taken = String::new();
+    while let Some(&c) = chars.peek() && predicate(c) {
+        taken.push(c);
+        chars.next();
+    }
+    taken
+}
+
+fn skip_spaces(chars: &mut Peekable<Chars>, line: &mut usize) {
+    while let Some(&c) = chars.peek() && c.is_whitespace() {
+        if c == '\n' {
+            *line += 1;
+        }
+        chars.next();
+    }
+}
+
+fn skip_line(chars: &mut Peekable<Chars>) {
+    while let Some(&c) = chars.peek() && c != '\n' {
+        chars.next();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::square::Square;
+    use crate::standard::piece::PieceColor;
+    use crate::standard::result::WinReason;
+    use super::*;
+
+    const GAMES: &str = r#"[Event "F/S Return Match"]
+[Site "Belgrade, Serbia JUG"]
+[Date "1992.11.04"]
+[Round "29"]
+[White "Fischer, Robert J."]
+[Black "Spassky, Boris V."]
+[Result "1/2-1/2"]
+[Annotator "Someone \"quoted\""]
+
+1. e4 e5 2. Nf3 Nc6 3. Bb5 {This opening is called the Ruy Lopez.} 3... a6
+4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 8. c3 O-O 9. h3 Nb8 10. d4 Nbd7
+11. c4 c6 12. cxb5 axb5 13. Nc3 Bb7 14. Bg5 b4 15. Nb1 h6 16. Bh4 c5 17. dxe5
+Nxe4 18. Bxe7 Qxe7 19. exd6 Qf6 20. Nbd2 Nxd6 21. Nc4 Nxc4 22. Bxc4 Nb6
+23. Ne5 Rae8 24. Bxf7+ Rxf7 25. Nxf7 Rxe1+ 26. Qxe1 Kxf7 27. Qe3 Qg5 28. Qxg5
+hxg5 29. b3 Ke6 30. a3 Kd6 31. axb4 cxb4 32. Ra5 Nd5 33. f3 Bc8 34. Kf2 Bf5
+35. Ra7 g6 36. Ra6+ Kc5 37. Ke1 Nf4 38. g3 Nxh3 39. Kd2 Kb5 40. Rd6 Kc5 41. Ra6
+Nf2 42. g4 Bd3 43. Re6 1/2-1/2
+
+[Event "Casual"]
+[Site "?"]
+[Date "????.??.??"]
+[Round "?"]
+[White "A"]
+[Black "B"]
+[Result "0-1"]
+
+% This line is escaped
+1.f3 e5 ; a rest of line comment
+2.g4?? (2. e4 $1 Nc6 (2... Qh4+ 3. g3) 3. Nc3) 2...Qh4# 0-1
+"#;
+
+    #[test]
+    fn read_multiple_games() {
+        let games = read_pgn(GAMES).unwrap();
+        assert_eq!(games.len(), 2);
+
+        let first = &games[0];
+        assert_eq!(first.tag("White"), Some("Fischer, Robert J."));
+        assert_eq!(first.tag("Annotator"), Some("Someone \"quoted\""));
+        assert_eq!(first.result(), "1/2-1/2");
+        assert_eq!(first.moves().len(), 85);
+        assert_eq!(first.moves()[4].comment(), Some("This opening is called the Ruy Lopez."));
+        let game = first.to_game().unwrap();
+        assert_eq!(game.position().to_fen(), "8/8/4R1p1/2k3p1/1p4P1/1P1b1P2/3K1n2/8 b - - 2 43");
+
+        let second = &games[1];
+        assert_eq!(second.moves().len(), 4);
+        assert_eq!(second.moves()[2].nags(), &[4]);
+        let variations = second.moves()[2].variations();
+        assert_eq!(variations.len(), 1);
+        assert_eq!(variations[0].len(), 3);
+        assert_eq!(variations[0][0].nags(), &[1]);
+        assert_eq!(variations[0][1].variations()[0].len(), 2);
+        assert_eq!(second.to_game().unwrap().result(), Some(GameResult::BlackWins(WinReason::Checkmate)));
+    }
+
+    #[test]
+    fn export_round_trip() {
+        let games = read_pgn(GAMES).unwrap();
+        let exported = write_pgn(&games);
+        assert!(exported.lines().all(|line| line.len() <= LINE_LENGTH));
+        // The seven tag roster comes first
+        assert!(exported.starts_with("[Event \"F/S Return Match\"]\n[Site \"Belgrade, Serbia JUG\"]\n"));
+        assert!(exported.contains("[Result \"1/2-1/2\"]\n[Annotator \"Someone \\\"quoted\\\"\"]\n\n1. e4 e5"));
+        assert!(exported.contains("2. g4 $4 (2. e4 $1 Nc6 (2... Qh4+ 3. g3) 3. Nc3) 2... Qh4# 0-1"));
+
+        let again = read_pgn(&exported).unwrap();
+        assert_eq!(again.len(), games.len());
+        for (a, b) in games.iter().zip(again.iter()) {
+            assert_eq!(a.moves(), b.moves());
+            assert_eq!(a.tags(), b.tags());
+        }
+        assert_eq!(write_pgn(&again), exported);
+    }
+
+    #[test]
+    fn custom_start_position() {
+        let pgn = "[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 1\"]\n[SetUp \"1\"]\n\n1... Kd7 2. e4 *";
+        let mut games = read_pgn(pgn).unwrap();
+        assert_eq!(games[0].moves().len(), 2);
+        assert_eq!(games[0].start().side_to_move(), PieceColor::Black);
+        assert!(games[0].to_pgn().contains("1... Kd7 2. e4 *"));
+
+        // Moves can be added to a game that was read, continuing from its last position
+        assert!(games[0].push_move(StandardMove::new(Square::parse("e4").unwrap(), Square::parse("e5").unwrap())).is_err());
+        games[0].push_move(StandardMove::new(Square::parse("d7").unwrap(), Square::parse("e6").unwrap())).unwrap();
+        assert!(games[0].to_pgn().contains("1... Kd7 2. e4 Ke6 *"));
+    }
+
+    #[test]
+    fn termination_marker_decides_result() {
+        let games = read_pgn("[Result \"1-0\"]\n\n1. f3 e5 2. g4 Qh4# 0-1\n\n[Result \"?\"]\n\n1. e4 1/2-1/2").unwrap();
+        assert_eq!(games[0].result(), "0-1");
+        assert!(games[0].to_pgn().contains("[Result \"0-1\"]"));
+        assert_eq!(games[1].result(), "1/2-1/2");
+    }
+
+    #[test]
+    fn games_without_termination_marker() {
+        let games = read_pgn("[Event \"One\"]\n\n1. e4 e5\n\n[Event \"Two\"]\n[White \"A\"]\n\n1. d4 *\n\n[Event \"Three\"]\n\n1. c4").unwrap();
+        assert_eq!(games.len(), 3);
+        assert_eq!(games[0].tag("Event"), Some("One"));
+        assert_eq!(games[0].result(), "*");
+        assert_eq!(games[1].tag("Event"), Some("Two"));
+        assert_eq!(games[1].tag("White"), Some("A"));
+        assert_eq!(games[1].moves().len(), 1);
+        assert_eq!(games[2].tag("Event"), Some("Three"));
+        assert_eq!(games[2].moves().len(), 1);
+    }
+
+    #[test]
+    fn build_and_export() {
+        let mut game = PgnGame::new(StandardPosition::starting_position());
+        game.set_tag("White", "Me");
+        for (from, to) in [("f2", "f3"), ("e7", "e5"), ("g2", "g4"), ("d8", "h4")] {
+            game.push_move(StandardMove::new(Square::parse(from).unwrap(), Square::parse(to).unwrap())).unwrap();
+        }
+        assert!(game.push_move(StandardMove::new(Square::parse("e2").unwrap(), Square::parse("e4").unwrap())).is_err());
+        game.set_result(game.to_game().unwrap().result());
+
+        let pgn = game.to_pgn();
+        assert!(pgn.starts_with("[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n[White \"Me\"]"));
+        assert!(pgn.ends_with("\n\n1. f3 e5 2. g4 Qh4# 0-1\n\n"));
+    }
+
+    #[test]
+    fn errors_report_location() {
+        let error = |pgn: &str| format!("{:#}", read_pgn(pgn).unwrap_err());
+
+        let message = error("1. e4 e5 *\n\n1. e4 e5 2. Ke3 *");
+        assert!(message.contains("game 2"), "{message}");
+        assert!(message.contains("ply 3"), "{message}");
+
+        let message = error("1. e4 (1. d4 d5 2. Qd3 Qd3 *");
+        assert!(message.contains("game 1"), "{message}");
+        assert!(message.contains("ply 4"), "{message}");
+
+        assert!(error("1. e4 { unterminated").contains("Comment is not closed"));
+        assert!(error("[Event \"Broken]\n1. e4 *").contains("not closed"));
+        assert!(error("1. e4 e5 *\n1. e4 ) *").contains("game 2"));
+        assert!(error("[FEN \"bad\"]\n*").contains("Invalid FEN tag"));
+    }
+}
@@ -9,6 +9,33 @@ pub enum StandardPiece {
     King,
 }
 
+impl StandardPiece {
+    /// The uppercase letter used for this piece in English notation, e.g. `N` for a knight.
+    pub fn letter(self) -> char {
+        match self {
+            StandardPiece::Pawn => 'P',
+            StandardPiece::Knight => 'N',
+            StandardPiece::Bishop => 'B',
+            StandardPiece::Rook => 'R',
+            StandardPiece::Queen => 'Q',
+            StandardPiece::King => 'K',
+        }
+    }
+
+    /// The piece belonging to a letter in English notation. Both uppercase and lowercase letters are accepted.
+    pub fn from_letter(letter: char) -> Option<Self> {
+        match letter.to_ascii_uppercase() {
+            'P' => Some(StandardPiece::Pawn),
+            'N' => Some(StandardPiece::Knight),
+            'B' => Some(StandardPiece::Bishop),
+            'R' => Some(StandardPiece::Rook),
+            'Q' => Some(StandardPiece::Queen),
+            'K' => Some(StandardPiece::King),
+            _ => None,
+        }
+    }
+}
+
 /// The color of a piece.
 /// Skaki will not support variants involving more than two colors.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
@@ -17,6 +44,16 @@ pub enum PieceColor {
     Black
 }
 
+impl PieceColor {
+    /// The color of the opponent.
+    pub fn opposite(self) -> Self {
+        match self {
+            PieceColor::White => PieceColor::Black,
+            PieceColor::Black => PieceColor::White,
+        }
+    }
+}
+
 /// A piece with an associated color (either white or black)
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct ColoredStandardPiece {
@@ -25,7 +62,7 @@ pub struct ColoredStandardPiece {
 }
 
 impl ColoredStandardPiece {
-    pub fn new(piece: StandardPiece, color: PieceColor) -> Self {
+    pub const fn new(piece: StandardPiece, color: PieceColor) -> Self {
         ColoredStandardPiece { piece, color }
     }
 
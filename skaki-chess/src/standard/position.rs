@@ -0,0 +1,737 @@
+use anyhow::{ensure, Context};
+use crate::board::Board;
+use crate::board::mailbox::MailboxBoard;
+use crate::square::Square;
+use crate::standard::moves::StandardMove;
+use crate::standard::piece::{ColoredStandardPiece, PieceColor, StandardPiece};
+use crate::standard::validator::attack::find_king;
+use crate::standard::validator::en_passant_capture_square;
+use crate::standard::zobrist::ZobristKeys;
+
+/// The direction a king can castle in.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum CastlingSide {
+    /// Castling towards the rook with the highest column index (the h-file on a regular board).
+    KingSide,
+    /// Castling towards the rook with the lowest column index (the a-file on a regular board).
+    QueenSide,
+}
+
+/// Keeps track of which castling moves are still available to each player.
+/// Instead of a simple flag, the column of the rook that is allowed to castle is stored. On a regular board this is
+/// always the a- or h-file, but this way nonstandard board sizes and starting positions are supported too.
+/// Wherever the king and rook start, they end up on the same squares as in a regular game, like in Chess960.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct CastlingRights {
+    white_king_side: Option<u16>,
+    white_queen_side: Option<u16>,
+    black_king_side: Option<u16>,
+    black_queen_side: Option<u16>,
+}
+
+impl CastlingRights {
+    /// No castling rights for either player.
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Full castling rights for both players, with the rooks in the corners of a board with the given width.
+    pub fn standard(width: u16) -> Self {
+        let king_side = Some(width - 1);
+        let queen_side = Some(0);
+        Self {
+            white_king_side: king_side,
+            white_queen_side: queen_side,
+            black_king_side: king_side,
+            black_queen_side: queen_side,
+        }
+    }
+
+    /// The column of the rook that is allowed to castle with, or `None` if this castling move is no longer available.
+    pub fn rook_column(&self, color: PieceColor, side: CastlingSide) -> Option<u16> {
+        match (color, side) {
+            (PieceColor::White, CastlingSide::KingSide) => self.white_king_side,
+            (PieceColor::White, CastlingSide::QueenSide) => self.white_queen_side,
+            (PieceColor::Black, CastlingSide::KingSide) => self.black_king_side,
+            (PieceColor::Black, CastlingSide::QueenSide) => self.black_queen_side,
+        }
+    }
+
+    /// Whether the given player can still castle to the given side.
+    pub fn has(&self, color: PieceColor, side: CastlingSide) -> bool {
+        self.rook_column(color, side).is_some()
+    }
+
+    /// Whether neither player has any castling rights left.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::none()
+    }
+
+    /// Set the castling right for a player to castle with the rook in the given column.
+    /// Pass `None` to remove the castling right instead.
+    pub fn set(&mut self, color: PieceColor, side: CastlingSide, rook_column: Option<u16>) {
+        let right = match (color, side) {
+            (PieceColor::White, CastlingSide::KingSide) => &mut self.white_king_side,
+            (PieceColor::White, CastlingSide::QueenSide) => &mut self.white_queen_side,
+            (PieceColor::Black, CastlingSide::KingSide) => &mut self.black_king_side,
+            (PieceColor::Black, CastlingSide::QueenSide) => &mut self.black_queen_side,
+        };
+        *right = rook_column;
+    }
+
+    /// Remove the castling right of a player on one side.
+    pub fn remove(&mut self, color: PieceColor, side: CastlingSide) {
+        self.set(color, side, None);
+    }
+
+    /// Remove all castling rights of a player.
+    pub fn remove_all(&mut self, color: PieceColor) {
+        self.remove(color, CastlingSide::KingSide);
+        self.remove(color, CastlingSide::QueenSide);
+    }
+}
+
+/// Everything needed to take back a move made with `StandardPosition::make`.
+/// The undo record only stores what the move changed, so reverting a move does not require a copy of the board.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Undo {
+    mov: StandardMove,
+    piece: ColoredStandardPiece,
+    // Where the piece ended up, which is not the destination of the move when the king castles by moving onto its rook.
+    to: Square,
+    captured: Option<(Square, ColoredStandardPiece)>,
+    castling_rook: Option<(Square, Square, ColoredStandardPiece)>,
+    castling_rights: CastlingRights,
+    en_passant: Option<Square>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    hash: Option<u64>,
+}
+
+impl Undo {
+    /// The move this record takes back.
+    pub fn mov(&self) -> &StandardMove {
+        &self.mov
+    }
+
+    /// The piece captured by the move and the square it stood on, which differs from the destination for en passant.
+    pub fn captured(&self) -> Option<(Square, &ColoredStandardPiece)> {
+        self.captured.as_ref().map(|(square, piece)| (*square, piece))
+    }
+}
+
+/// The full state of a standard game of chess at one point in time.
+/// Next to the placement of the pieces on the board, this contains everything needed to decide which moves are legal
+/// (side to move, castling rights and the en passant square), as well as the clocks used for the draw rules.
+#[derive(Debug, Clone)]
+pub struct StandardPosition<B: Board<Token = ColoredStandardPiece>> {
+    board: B,
+    side_to_move: PieceColor,
+    castling_rights: CastlingRights,
+    en_passant: Option<Square>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    zobrist_keys: &'static ZobristKeys,
+    // The Zobrist hash of the position, or `None` if the board was changed directly and it has to be recomputed.
+    hash: Option<u64>,
+}
+
+impl<B: Board<Token = ColoredStandardPiece>> StandardPosition<B> {
+    /// Creates a position with the given board, with white to move and no castling rights or en passant square.
+    pub fn new(board: B) -> Self {
+        let mut position = Self {
+            board,
+            side_to_move: PieceColor::White,
+            castling_rights: CastlingRights::none(),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            zobrist_keys: ZobristKeys::default_keys(),
+            hash: None,
+        };
+        position.hash = Some(position.zobrist_keys.hash(&position));
+        position
+    }
+
+    /// The board with the pieces in this position.
+    pub fn board(&self) -> &B {
+        &self.board
+    }
+
+    /// Mutable access to the board. Note that changing the board directly does not update any of the
+    /// other state in the position.
+    pub fn board_mut(&mut self) -> &mut B {
+        // The hash is recomputed from scratch the next time it is needed.
+        self.hash = None;
+        &mut self.board
+    }
+
+    /// The Zobrist hash of this position. It is kept up to date as moves are made and taken back.
+    pub fn hash(&self) -> u64 {
+        self.hash.unwrap_or_else(|| self.zobrist_keys.hash(self))
+    }
+
+    /// The keys used for the Zobrist hash of this position.
+    pub fn zobrist_keys(&self) -> &'static ZobristKeys {
+        self.zobrist_keys
+    }
+
+    /// Changes the keys used for the Zobrist hash, for example to the Polyglot keys to look up opening books.
+    pub fn set_zobrist_keys(&mut self, keys: &'static ZobristKeys) {
+        self.zobrist_keys = keys;
+        self.hash = Some(keys.hash(self));
+    }
+
+    // Changes the castling rights, en passant square or side to move, while keeping the hash up to date.
+    fn update_state(&mut self, update: impl FnOnce(&mut Self)) {
+        let before = self.zobrist_keys.state(self);
+        update(self);
+        let after = self.zobrist_keys.state(self);
+        if let Some(hash) = &mut self.hash {
+            *hash ^= before ^ after;
+        }
+    }
+
+    // Toggles a piece on a square in the hash.
+    fn toggle_piece(&mut self, piece: &ColoredStandardPiece, square: Square) {
+        if let Some(hash) = &mut self.hash {
+            *hash ^= self.zobrist_keys.piece(piece, square);
+        }
+    }
+
+    /// The player that makes the next move.
+    pub fn side_to_move(&self) -> PieceColor {
+        self.side_to_move
+    }
+
+    pub fn set_side_to_move(&mut self, color: PieceColor) {
+        self.update_state(|position| position.side_to_move = color);
+    }
+
+    /// The castling moves that are still available to both players.
+    /// Note that having a castling right does not mean castling is legal right now.
+    pub fn castling_rights(&self) -> CastlingRights {
+        self.castling_rights
+    }
+
+    pub fn set_castling_rights(&mut self, rights: CastlingRights) {
+        self.update_state(|position| position.castling_rights = rights);
+    }
+
+    /// The square a pawn skipped over with a double step in the previous move, if any.
+    /// An enemy pawn may capture on this square en passant.
+    pub fn en_passant(&self) -> Option<Square> {
+        self.en_passant
+    }
+
+    pub fn set_en_passant(&mut self, square: Option<Square>) {
+        self.update_state(|position| position.en_passant = square);
+    }
+
+    /// The number of halfmoves since the last capture or pawn move.
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    pub fn set_halfmove_clock(&mut self, clock: u32) {
+        self.halfmove_clock = clock;
+    }
+
+    /// The number of the current full move. Starts at 1, and is incremented after each move by black.
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    pub fn set_fullmove_number(&mut self, number: u32) {
+        self.fullmove_number = number;
+    }
+
+    /// Plays a move, updating both the board and the rest of the game state.
+    /// Like `Board::make_move`, this does not check the legality of the move in any way.
+    ///
+    /// Returns Err(_) if there is no piece on the starting square, or either square is outside the board.
+    pub fn make_move(&mut self, mov: &StandardMove) -> anyhow::Result<()> {
+        self.make(mov).map(|_| ())
+    }
+
+    /// Plays a move like `make_move`, and returns a record that `unmake` can use to take the move back again.
+    pub fn make(&mut self, mov: &StandardMove) -> anyhow::Result<Undo> {
+        let piece = self.board.at(mov.from()).context("There is no piece on the starting square of the move")?;
+        ensure!(self.board.valid_square(mov.to()), "Destination square is not inside the board");
+        let to = self.castling_king_square(mov).unwrap_or(mov.to());
+        if self.hash.is_none() {
+            self.hash = Some(self.zobrist_keys.hash(self));
+        }
+        let state_before = self.zobrist_keys.state(self);
+        let mut undo = Undo {
+            mov: *mov,
+            piece: piece.clone(),
+            to,
+            captured: None,
+            castling_rook: None,
+            castling_rights: self.castling_rights,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            hash: self.hash,
+        };
+
+        // When castling, the rook is lifted off the board first, since on some boards the king could land on its square.
+        undo.castling_rook = match self.castling_rook_squares(mov) {
+            Some((rook_from, rook_to)) => self.board.at(rook_from).map(|rook| (rook_from, rook_to, rook)),
+            None => None,
+        };
+        if let Some((rook_from, _, rook)) = &undo.castling_rook {
+            self.board.set(*rook_from, None)?;
+            self.toggle_piece(rook, *rook_from);
+        }
+
+        // En passant captures a pawn that is not on the destination square.
+        if let Some(square) = en_passant_capture_square(&self.board, mov, self.en_passant) {
+            undo.captured = self.board.at(square).map(|pawn| (square, pawn));
+            self.board.set(square, None)?;
+        }
+
+        if let Some(captured) = self.board.make_move(mov.from(), to)? {
+            undo.captured = Some((to, captured));
+        }
+        // A promoting pawn is replaced by the new piece.
+        let placed = match mov.promotion() {
+            Some(promotion) => ColoredStandardPiece::new(promotion, piece.color()),
+            None => piece.clone(),
+        };
+        if mov.promotion().is_some() {
+            self.board.set(to, Some(placed.clone()))?;
+        }
+        if let Some((square, captured)) = &undo.captured {
+            self.toggle_piece(captured, *square);
+        }
+        self.toggle_piece(&piece, mov.from());
+        self.toggle_piece(&placed, to);
+
+        if let Some((_, rook_to, rook)) = &undo.castling_rook {
+            self.board.set(*rook_to, Some(rook.clone()))?;
+            self.toggle_piece(rook, *rook_to);
+        }
+
+        // Moving the king loses all castling rights, moving a rook only loses the right for that rook.
+        // Capturing a rook that could still castle also takes away that right from the opponent.
+        if piece.piece() == StandardPiece::King {
+            self.castling_rights.remove_all(piece.color());
+        }
+        self.remove_castling_right_at(piece.color(), mov.from());
+        self.remove_castling_right_at(piece.color().opposite(), to);
+
+        // After a double step, the skipped square becomes available for en passant.
+        self.en_passant = None;
+        if piece.piece() == StandardPiece::Pawn && u16::abs_diff(mov.from().row, to.row) == 2 {
+            self.en_passant = Some(Square::new((mov.from().row + to.row) / 2, mov.from().column));
+        }
+
+        // The castling rook was already lifted off the board, so castling never counts as a capture.
+        if piece.piece() == StandardPiece::Pawn || undo.captured.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+
+        if self.side_to_move == PieceColor::Black {
+            self.fullmove_number += 1;
+        }
+        self.side_to_move = self.side_to_move.opposite();
+
+        let state_after = self.zobrist_keys.state(self);
+        if let Some(hash) = &mut self.hash {
+            *hash ^= state_before ^ state_after;
+        }
+
+        Ok(undo)
+    }
+
+    /// Takes back a move, restoring the position to exactly what it was before `make` returned this record.
+    /// Moves must be taken back in the reverse order they were made in.
+    ///
+    /// Returns Err(_) if the squares of the move are outside the board, which means the record is from another position.
+    pub fn unmake(&mut self, undo: Undo) -> anyhow::Result<()> {
+        let mov = undo.mov;
+        // Clear the destination squares first, since with castling the king and rook may swap places.
+        if let Some((_, rook_to, _)) = &undo.castling_rook {
+            self.board.set(*rook_to, None)?;
+        }
+        self.board.set(undo.to, None)?;
+
+        self.board.set(mov.from(), Some(undo.piece))?;
+        if let Some((rook_from, _, rook)) = undo.castling_rook {
+            self.board.set(rook_from, Some(rook))?;
+        }
+        if let Some((square, captured)) = undo.captured {
+            self.board.set(square, Some(captured))?;
+        }
+
+        self.side_to_move = self.side_to_move.opposite();
+        self.castling_rights = undo.castling_rights;
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.fullmove_number = undo.fullmove_number;
+        self.hash = undo.hash;
+        Ok(())
+    }
+
+    /// The row the pieces of this color start on, and where castling takes place.
+    pub fn back_rank(&self, color: PieceColor) -> u16 {
+        match color {
+            PieceColor::White => 0,
+            PieceColor::Black => self.board.height() - 1,
+        }
+    }
+
+    /// The move that castles the king of the given color to the given side, or `None` if the player has lost that
+    /// castling right. This does not check whether castling is actually allowed right now.
+    /// Castling is written as the king moving to the square it ends up on, except when the king moves less than two
+    /// squares, which can happen in Chess960. To tell it apart from a regular king move, castling is then written as
+    /// the king moving onto the rook it castles with.
+    pub fn castling_move(&self, color: PieceColor, side: CastlingSide) -> Option<StandardMove> {
+        let king = find_king(&self.board, color)?;
+        self.castling_move_of(king, color, side)
+    }
+
+    fn castling_move_of(&self, king: Square, color: PieceColor, side: CastlingSide) -> Option<StandardMove> {
+        let rook_column = self.castling_rights.rook_column(color, side)?;
+        if king.row != self.back_rank(color) {
+            return None;
+        }
+        let (king_column, _) = self.castling_columns(side)?;
+        if king.column.abs_diff(king_column) >= 2 {
+            Some(StandardMove::new(king, Square::new(king.row, king_column)))
+        } else {
+            Some(StandardMove::new(king, Square::new(king.row, rook_column)))
+        }
+    }
+
+    // The columns the king and rook end up on after castling to the given side. These are the same in every starting
+    // position: the king lands on the c-file or the second to last file, with the rook on the inside next to it.
+    fn castling_columns(&self, side: CastlingSide) -> Option<(u16, u16)> {
+        let (king, rook) = match side {
+            CastlingSide::KingSide => (self.board.width().checked_sub(2)?, self.board.width().checked_sub(3)?),
+            CastlingSide::QueenSide => (2, 3),
+        };
+        (king < self.board.width() && rook < self.board.width()).then_some((king, rook))
+    }
+
+    /// If the move is a castling move, returns the side the king castles to.
+    /// This is the case when the move is exactly the one returned by `castling_move`, so the player must still have
+    /// the castling right. Whether castling is actually allowed right now is not checked.
+    pub fn castling_side(&self, mov: &StandardMove) -> Option<CastlingSide> {
+        let piece = self.board.get(mov.from())?;
+        if piece.piece() != StandardPiece::King {
+            return None;
+        }
+
+        [CastlingSide::KingSide, CastlingSide::QueenSide]
+            .into_iter()
+            .find(|&side| self.castling_move_of(mov.from(), piece.color(), side) == Some(*mov))
+    }
+
+    /// For a castling move, returns the square the king ends up on. This is the destination of the move, unless the
+    /// king castles by moving onto its rook.
+    /// Returns `None` if the move is not a castling move.
+    pub fn castling_king_square(&self, mov: &StandardMove) -> Option<Square> {
+        let (king_column, _) = self.castling_columns(self.castling_side(mov)?)?;
+        Some(Square::new(mov.from().row, king_column))
+    }
+
+    /// For a castling move, returns the squares the rook moves from and to.
+    /// Returns `None` if the move is not a castling move.
+    pub fn castling_rook_squares(&self, mov: &StandardMove) -> Option<(Square, Square)> {
+        let side = self.castling_side(mov)?;
+        let color = self.board.get(mov.from())?.color();
+        let rook_column = self.castling_rights.rook_column(color, side)?;
+        let (_, rook_to) = self.castling_columns(side)?;
+        let row = mov.from().row;
+        Some((Square::new(row, rook_column), Square::new(row, rook_to)))
+    }
+
+    // Removes a castling right of the given player if its rook starts on this square.
+    fn remove_castling_right_at(&mut self, color: PieceColor, square: Square) {
+        if square.row != self.back_rank(color) {
+            return;
+        }
+
+        for side in [CastlingSide::KingSide, CastlingSide::QueenSide] {
+            if self.castling_rights.rook_column(color, side) == Some(square.column) {
+                self.castling_rights.remove(color, side);
+            }
+        }
+    }
+}
+
+impl StandardPosition<MailboxBoard<ColoredStandardPiece>> {
+    /// The starting position of a regular game of chess, on an 8x8 board.
+    pub fn starting_position() -> Self {
+        let mut board = MailboxBoard::new(8, 8);
+        let back_rank = [
+            StandardPiece::Rook, StandardPiece::Knight, StandardPiece::Bishop, StandardPiece::Queen,
+            StandardPiece::King, StandardPiece::Bishop, StandardPiece::Knight, StandardPiece::Rook,
+        ];
+
+        for (column, piece) in back_rank.into_iter().enumerate() {
+            let column = column as u16;
+            board.set(Square::new(0, column), Some(ColoredStandardPiece::new(piece, PieceColor::White)))
+                .expect("Index in range");
+            board.set(Square::new(1, column), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::White)))
+                .expect("Index in range");
+            board.set(Square::new(6, column), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::Black)))
+                .expect("Index in range");
+            board.set(Square::new(7, column), Some(ColoredStandardPiece::new(piece, PieceColor::Black)))
+                .expect("Index in range");
+        }
+
+        let mut position = Self::new(board);
+        position.set_castling_rights(CastlingRights::standard(8));
+        position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generator::MoveGenerator;
+    use crate::standard::generator::StandardMoveGenerator;
+    use super::*;
+
+    fn play(position: &mut StandardPosition<MailboxBoard<ColoredStandardPiece>>, from: &str, to: &str) {
+        let mov = StandardMove::new(Square::parse(from).unwrap(), Square::parse(to).unwrap());
+        position.make_move(&mov).expect("Move should be applied");
+    }
+
+    #[test]
+    fn starting_position() {
+        let position = StandardPosition::starting_position();
+        assert_eq!(position.side_to_move(), PieceColor::White);
+        assert_eq!(position.castling_rights(), CastlingRights::standard(8));
+        assert_eq!(position.en_passant(), None);
+        assert_eq!(position.halfmove_clock(), 0);
+        assert_eq!(position.fullmove_number(), 1);
+
+        let board = position.board();
+        assert_eq!(board.at(Square::parse("e1").unwrap()), Some(ColoredStandardPiece::new(StandardPiece::King, PieceColor::White)));
+        assert_eq!(board.at(Square::parse("d8").unwrap()), Some(ColoredStandardPiece::new(StandardPiece::Queen, PieceColor::Black)));
+        assert_eq!(board.at(Square::parse("c7").unwrap()), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::Black)));
+        assert_eq!(board.at(Square::parse("e4").unwrap()), None);
+    }
+
+    #[test]
+    fn move_updates_state() {
+        let mut position = StandardPosition::starting_position();
+
+        play(&mut position, "e2", "e4");
+        assert_eq!(position.board().at(Square::parse("e2").unwrap()), None);
+        assert_eq!(position.board().at(Square::parse("e4").unwrap()), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::White)));
+        assert_eq!(position.side_to_move(), PieceColor::Black);
+        assert_eq!(position.en_passant(), Some(Square::parse("e3").unwrap()));
+        assert_eq!(position.fullmove_number(), 1);
+
+        play(&mut position, "g8", "f6");
+        assert_eq!(position.side_to_move(), PieceColor::White);
+        assert_eq!(position.en_passant(), None);
+        assert_eq!(position.halfmove_clock(), 1);
+        assert_eq!(position.fullmove_number(), 2);
+
+        play(&mut position, "g1", "f3");
+        assert_eq!(position.halfmove_clock(), 2);
+
+        // Captures reset the halfmove clock
+        play(&mut position, "f6", "e4");
+        assert_eq!(position.halfmove_clock(), 0);
+        assert_eq!(position.fullmove_number(), 3);
+    }
+
+    #[test]
+    fn castling_rights_lost() {
+        let mut position = StandardPosition::starting_position();
+        // Clear the way for the pieces
+        for square in ["a2", "h2", "e2", "b8", "c8", "d8"] {
+            position.board_mut().set(Square::parse(square).unwrap(), None).unwrap();
+        }
+
+        // Moving a rook loses the castling right on that side only
+        play(&mut position, "h1", "h4");
+        assert!(!position.castling_rights().has(PieceColor::White, CastlingSide::KingSide));
+        assert!(position.castling_rights().has(PieceColor::White, CastlingSide::QueenSide));
+
+        // Moving the king loses all castling rights
+        play(&mut position, "e8", "d8");
+        assert!(!position.castling_rights().has(PieceColor::Black, CastlingSide::KingSide));
+        assert!(!position.castling_rights().has(PieceColor::Black, CastlingSide::QueenSide));
+
+        // Having the rook captured loses the right as well
+        let mut position = StandardPosition::starting_position();
+        position.board_mut().set(Square::parse("a2").unwrap(), None).unwrap();
+        position.board_mut().set(Square::parse("a7").unwrap(), None).unwrap();
+        play(&mut position, "a1", "a8");
+        assert!(!position.castling_rights().has(PieceColor::White, CastlingSide::QueenSide));
+        assert!(!position.castling_rights().has(PieceColor::Black, CastlingSide::QueenSide));
+        assert!(position.castling_rights().has(PieceColor::Black, CastlingSide::KingSide));
+    }
+
+    #[test]
+    fn castling_moves_rook() {
+        let mut position = StandardPosition::starting_position();
+        for square in ["f1", "g1", "b8", "c8", "d8"] {
+            position.board_mut().set(Square::parse(square).unwrap(), None).unwrap();
+        }
+
+        let king_side = StandardMove::castle(Square::parse("e1").unwrap(), CastlingSide::KingSide).unwrap();
+        assert_eq!(king_side, StandardMove::new(Square::parse("e1").unwrap(), Square::parse("g1").unwrap()));
+        assert_eq!(position.castling_side(&king_side), Some(CastlingSide::KingSide));
+        position.make_move(&king_side).unwrap();
+        assert_eq!(position.board().at(Square::parse("g1").unwrap()), Some(ColoredStandardPiece::new(StandardPiece::King, PieceColor::White)));
+        assert_eq!(position.board().at(Square::parse("f1").unwrap()), Some(ColoredStandardPiece::new(StandardPiece::Rook, PieceColor::White)));
+        assert_eq!(position.board().at(Square::parse("e1").unwrap()), None);
+        assert_eq!(position.board().at(Square::parse("h1").unwrap()), None);
+        assert!(!position.castling_rights().has(PieceColor::White, CastlingSide::QueenSide));
+        assert_eq!(position.halfmove_clock(), 1);
+
+        play(&mut position, "e8", "c8");
+        assert_eq!(position.board().at(Square::parse("c8").unwrap()), Some(ColoredStandardPiece::new(StandardPiece::King, PieceColor::Black)));
+        assert_eq!(position.board().at(Square::parse("d8").unwrap()), Some(ColoredStandardPiece::new(StandardPiece::Rook, PieceColor::Black)));
+        assert_eq!(position.board().at(Square::parse("a8").unwrap()), None);
+        assert!(position.castling_rights().is_empty());
+
+        // A king step of two squares that is not on the back rank is not castling
+        play(&mut position, "g1", "g2");
+        assert_eq!(position.castling_side(&StandardMove::new(Square::parse("g2").unwrap(), Square::parse("e2").unwrap())), None);
+    }
+
+    // Castles on a copy of the position, and checks where the king and rook end up.
+    fn assert_castles(position: &StandardPosition<MailboxBoard<ColoredStandardPiece>>, side: CastlingSide, mov: (&str, &str), king: &str, rook: &str) {
+        let expected = StandardMove::new(Square::parse(mov.0).unwrap(), Square::parse(mov.1).unwrap());
+        let castling = position.castling_move(position.side_to_move(), side).unwrap();
+        assert_eq!(castling, expected);
+        assert_eq!(position.castling_side(&castling), Some(side));
+
+        let color = position.side_to_move();
+        let pieces = position.board().iter().filter(|(_, piece)| piece.color() == color).count();
+        let mut after = position.clone();
+        after.make_move(&castling).unwrap();
+        assert_eq!(after.board().at(Square::parse(king).unwrap()), Some(ColoredStandardPiece::new(StandardPiece::King, color)));
+        assert_eq!(after.board().at(Square::parse(rook).unwrap()), Some(ColoredStandardPiece::new(StandardPiece::Rook, color)));
+        assert_eq!(after.board().iter().filter(|(_, piece)| piece.color() == color).count(), pieces);
+        assert_eq!(after.hash(), after.zobrist_keys().hash(&after));
+    }
+
+    #[test]
+    fn chess960_castling() {
+        // The king and rook always end up on the c- and d-file, or the g- and f-file. When the king moves less than
+        // two squares, castling is written as the king moving onto its rook.
+        let king_b1 = StandardPosition::from_fen("4k3/8/8/8/8/8/8/RK5R w HA - 0 1").unwrap();
+        assert_castles(&king_b1, CastlingSide::QueenSide, ("b1", "a1"), "c1", "d1");
+        assert_castles(&king_b1, CastlingSide::KingSide, ("b1", "g1"), "g1", "f1");
+
+        let king_g1 = StandardPosition::from_fen("4k3/8/8/8/8/8/8/R5KR w HA - 0 1").unwrap();
+        assert_castles(&king_g1, CastlingSide::KingSide, ("g1", "h1"), "g1", "f1");
+        assert_castles(&king_g1, CastlingSide::QueenSide, ("g1", "c1"), "c1", "d1");
+
+        // The king and rook swap places
+        let swap = StandardPosition::from_fen("1r3kr1/8/8/8/8/8/8/1R3KR1 b GBgb - 0 1").unwrap();
+        assert_castles(&swap, CastlingSide::KingSide, ("f8", "g8"), "g8", "f8");
+        assert_castles(&swap, CastlingSide::QueenSide, ("f8", "c8"), "c8", "d8");
+
+        // A king move of two squares is not castling if the king does not end up on the castling square.
+        let king_d1 = StandardPosition::from_fen("4k3/8/8/8/8/8/8/3K3R w H - 0 1").unwrap();
+        assert_castles(&king_d1, CastlingSide::KingSide, ("d1", "g1"), "g1", "f1");
+        assert_eq!(king_d1.castling_side(&StandardMove::new(Square::parse("d1").unwrap(), Square::parse("f1").unwrap())), None);
+        assert_eq!(king_d1.castling_move(PieceColor::White, CastlingSide::QueenSide), None);
+    }
+
+    #[test]
+    fn en_passant_removes_pawn() {
+        let mut position = StandardPosition::starting_position();
+        play(&mut position, "e2", "e4");
+        play(&mut position, "a7", "a6");
+        play(&mut position, "e4", "e5");
+        play(&mut position, "f7", "f5");
+        assert_eq!(position.en_passant(), Some(Square::parse("f6").unwrap()));
+
+        play(&mut position, "e5", "f6");
+        assert_eq!(position.board().at(Square::parse("f6").unwrap()), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::White)));
+        assert_eq!(position.board().at(Square::parse("f5").unwrap()), None);
+        assert_eq!(position.board().at(Square::parse("e5").unwrap()), None);
+        assert_eq!(position.en_passant(), None);
+    }
+
+    #[test]
+    fn promotion_replaces_pawn() {
+        let mut position = StandardPosition::new(MailboxBoard::new(8, 8));
+        position.board_mut().set(Square::parse("d7").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::White))).unwrap();
+        position.board_mut().set(Square::parse("e8").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Rook, PieceColor::Black))).unwrap();
+
+        position.make_move(&StandardMove::promote(Square::parse("d7").unwrap(), Square::parse("e8").unwrap(), StandardPiece::Knight)).unwrap();
+        assert_eq!(position.board().at(Square::parse("e8").unwrap()), Some(ColoredStandardPiece::new(StandardPiece::Knight, PieceColor::White)));
+        assert_eq!(position.board().at(Square::parse("d7").unwrap()), None);
+        assert_eq!(position.halfmove_clock(), 0);
+    }
+
+    #[test]
+    fn move_from_empty_square() {
+        let mut position = StandardPosition::starting_position();
+        let mov = StandardMove::new(Square::parse("e4").unwrap(), Square::parse("e5").unwrap());
+        assert!(position.make_move(&mov).is_err());
+    }
+
+    // Makes and takes back every legal move two plies deep, checking that the position is restored each time.
+    fn assert_unmake_restores(position: &mut StandardPosition<MailboxBoard<ColoredStandardPiece>>, depth: u32) {
+        if depth == 0 {
+            return;
+        }
+        let before = position.to_fen();
+        let generator = StandardMoveGenerator {};
+        for mov in generator.generate(position) {
+            let undo = position.make(&mov).expect("Generated moves are always playable");
+            assert_eq!(undo.mov(), &mov);
+            assert_unmake_restores(position, depth - 1);
+            position.unmake(undo).expect("Undo record belongs to this position");
+            assert_eq!(position.to_fen(), before, "after taking back {mov}");
+        }
+    }
+
+    #[test]
+    fn unmake_restores_position() {
+        for fen in [
+            crate::standard::fen::STARTING_FEN,
+            // Castling both ways, promotions and en passant
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            // Chess960 castling where the king and rook swap places on the king side
+            "1r3kr1/6p1/8/8/8/8/6P1/1R3KR1 w GBgb - 0 1",
+            // Chess960 castling where the king stays on g1 and only the rook moves
+            "1r4kr/8/8/8/8/8/8/1R4KR w HBhb - 0 1",
+        ] {
+            let mut position = StandardPosition::from_fen(fen).unwrap();
+            assert_unmake_restores(&mut position, 2);
+        }
+
+        // The swap really is one of the moves taken back above.
+        let position = StandardPosition::from_fen("1r3kr1/6p1/8/8/8/8/6P1/1R3KR1 w GBgb - 0 1").unwrap();
+        let swap = StandardMove::new(Square::parse("f1").unwrap(), Square::parse("g1").unwrap());
+        assert_eq!(position.castling_side(&swap), Some(CastlingSide::KingSide));
+        assert!(StandardMoveGenerator {}.generate(&position).contains(&swap));
+    }
+
+    #[test]
+    fn unmake_records_captures() {
+        let mut position = StandardPosition::starting_position();
+        play(&mut position, "e2", "e4");
+        play(&mut position, "d7", "d5");
+        let undo = position.make(&StandardMove::new(Square::parse("e4").unwrap(), Square::parse("d5").unwrap())).unwrap();
+        assert_eq!(undo.captured(), Some((Square::parse("d5").unwrap(), &ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::Black))));
+        assert_eq!(position.halfmove_clock(), 0);
+
+        position.unmake(undo).unwrap();
+        assert_eq!(position.side_to_move(), PieceColor::White);
+        assert_eq!(position.en_passant(), Some(Square::parse("d6").unwrap()));
+        assert_eq!(position.board().at(Square::parse("e4").unwrap()), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::White)));
+        assert_eq!(position.board().at(Square::parse("d5").unwrap()), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::Black)));
+    }
+}
@@ -0,0 +1,219 @@
+use crate::board::Board;
+use crate::generator::MoveGenerator;
+use crate::standard::generator::StandardMoveGenerator;
+use crate::standard::piece::{ColoredStandardPiece, PieceColor};
+use crate::standard::draw::{has_insufficient_material, FIFTY_MOVE_RULE, SEVENTY_FIVE_MOVE_RULE};
+use crate::standard::position::StandardPosition;
+use crate::standard::validator::attack::is_in_check;
+
+/// The way a player won the game.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum WinReason {
+    /// The king of the losing player is in check, and there is no legal move to get out of it.
+    Checkmate,
+}
+
+/// The reason a game ended in a draw.
+/// Some of these end the game immediately, while others only allow a player to claim a draw, see `is_claimable`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum DrawReason {
+    /// The player to move is not in check, but has no legal moves.
+    Stalemate,
+    /// Neither player has enough pieces left to checkmate the other.
+    InsufficientMaterial,
+    /// Fifty moves by each player without a capture or pawn move. Can be claimed by either player.
+    FiftyMoveRule,
+    /// Seventy-five moves by each player without a capture or pawn move.
+    SeventyFiveMoveRule,
+    /// The same position occurred three times. Can be claimed by either player.
+    ThreefoldRepetition,
+    /// The same position occurred five times.
+    FivefoldRepetition,
+}
+
+impl DrawReason {
+    /// Whether this draw has to be claimed by one of the players. All other draws end the game automatically.
+    pub fn is_claimable(&self) -> bool {
+        matches!(self, DrawReason::FiftyMoveRule | DrawReason::ThreefoldRepetition)
+    }
+}
+
+/// The final result of a game.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum GameResult {
+    WhiteWins(WinReason),
+    BlackWins(WinReason),
+    Draw(DrawReason),
+}
+
+impl GameResult {
+    /// The result of a game won by the given player.
+    pub fn win(winner: PieceColor, reason: WinReason) -> Self {
+        match winner {
+            PieceColor::White => GameResult::WhiteWins(reason),
+            PieceColor::Black => GameResult::BlackWins(reason),
+        }
+    }
+
+    /// The player that won the game, or `None` if the game was drawn.
+    pub fn winner(&self) -> Option<PieceColor> {
+        match self {
+            GameResult::WhiteWins(_) => Some(PieceColor::White),
+            GameResult::BlackWins(_) => Some(PieceColor::Black),
+            GameResult::Draw(_) => None,
+        }
+    }
+
+    pub fn is_draw(&self) -> bool {
+        matches!(self, GameResult::Draw(_))
+    }
+}
+
+/// Returns true if the player to move is in check and has no legal moves.
+pub fn is_checkmate<B: Board<Token = ColoredStandardPiece> + Clone>(position: &StandardPosition<B>) -> bool {
+    is_in_check(position.board(), position.side_to_move()) && !has_legal_move(position)
+}
+
+/// Returns true if the player to move is not in check, but has no legal moves.
+pub fn is_stalemate<B: Board<Token = ColoredStandardPiece> + Clone>(position: &StandardPosition<B>) -> bool {
+    !is_in_check(position.board(), position.side_to_move()) && !has_legal_move(position)
+}
+
+/// Determines whether the game is over in this position, and if so, what the result is.
+/// Only the rules that end the game automatically are taken into account, and since a single position knows nothing
+/// about the moves before it, repetitions are not detected either. See `StandardGame` for that.
+/// Returns `None` if the game is still going.
+pub fn game_result<B: Board<Token = ColoredStandardPiece> + Clone>(position: &StandardPosition<B>) -> Option<GameResult> {
+    // A checkmate takes precedence over the other rules, even if they apply at the same time.
+    if !has_legal_move(position) {
+        let color = position.side_to_move();
+        return if is_in_check(position.board(), color) {
+            Some(GameResult::win(color.opposite(), WinReason::Checkmate))
+        } else {
+            Some(GameResult::Draw(DrawReason::Stalemate))
+        };
+    }
+
+    if has_insufficient_material(position.board()) {
+        return Some(GameResult::Draw(DrawReason::InsufficientMaterial));
+    }
+    if position.halfmove_clock() >= SEVENTY_FIVE_MOVE_RULE {
+        return Some(GameResult::Draw(DrawReason::SeventyFiveMoveRule));
+    }
+
+    None
+}
+
+/// Returns the reason a player can claim a draw in this position, if there is one.
+/// Like `game_result`, this does not know about repetitions.
+pub fn claimable_draw<B: Board<Token = ColoredStandardPiece>>(position: &StandardPosition<B>) -> Option<DrawReason> {
+    (position.halfmove_clock() >= FIFTY_MOVE_RULE).then_some(DrawReason::FiftyMoveRule)
+}
+
+fn has_legal_move<B: Board<Token = ColoredStandardPiece> + Clone>(position: &StandardPosition<B>) -> bool {
+    let generator = StandardMoveGenerator {};
+    !generator.generate(position).is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::mailbox::MailboxBoard;
+    use crate::square::Square;
+    use crate::standard::moves::StandardMove;
+    use crate::standard::piece::StandardPiece;
+    use super::*;
+
+    fn play(position: &mut StandardPosition<MailboxBoard<ColoredStandardPiece>>, from: &str, to: &str) {
+        let mov = StandardMove::new(Square::parse(from).unwrap(), Square::parse(to).unwrap());
+        position.make_move(&mov).expect("Move should be applied");
+    }
+
+    #[test]
+    fn ongoing_game() {
+        let mut position = StandardPosition::starting_position();
+        assert_eq!(game_result(&position), None);
+        assert!(!is_checkmate(&position));
+        assert!(!is_stalemate(&position));
+
+        // Being in check is not the end of the game
+        for (from, to) in [("e2", "e4"), ("f7", "f6"), ("d1", "h5")] {
+            play(&mut position, from, to);
+        }
+        assert!(is_in_check(position.board(), PieceColor::Black));
+        assert_eq!(game_result(&position), None);
+        assert!(!is_checkmate(&position));
+    }
+
+    #[test]
+    fn checkmate() {
+        // Fool's mate
+        let mut position = StandardPosition::starting_position();
+        for (from, to) in [("f2", "f3"), ("e7", "e5"), ("g2", "g4"), ("d8", "h4")] {
+            play(&mut position, from, to);
+        }
+
+        assert!(is_checkmate(&position));
+        assert!(!is_stalemate(&position));
+        let result = game_result(&position).expect("The game is over");
+        assert_eq!(result, GameResult::BlackWins(WinReason::Checkmate));
+        assert_eq!(result.winner(), Some(PieceColor::Black));
+        assert!(!result.is_draw());
+    }
+
+    #[test]
+    fn stalemate() {
+        let mut position = StandardPosition::new(MailboxBoard::new(8, 8));
+        let board = position.board_mut();
+        board.set(Square::parse("h8").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::King, PieceColor::Black))).unwrap();
+        board.set(Square::parse("f7").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::King, PieceColor::White))).unwrap();
+        board.set(Square::parse("g6").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Queen, PieceColor::White))).unwrap();
+        position.set_side_to_move(PieceColor::Black);
+
+        assert!(is_stalemate(&position));
+        assert!(!is_checkmate(&position));
+        let result = game_result(&position).expect("The game is over");
+        assert_eq!(result, GameResult::Draw(DrawReason::Stalemate));
+        assert_eq!(result.winner(), None);
+        assert!(result.is_draw());
+        assert!(!DrawReason::Stalemate.is_claimable());
+    }
+
+    #[test]
+    fn move_rules() {
+        let mut position = StandardPosition::starting_position();
+        position.set_halfmove_clock(99);
+        assert_eq!(claimable_draw(&position), None);
+        position.set_halfmove_clock(100);
+        assert_eq!(claimable_draw(&position), Some(DrawReason::FiftyMoveRule));
+        assert!(DrawReason::FiftyMoveRule.is_claimable());
+        // The fifty move rule has to be claimed, so the game is not over yet
+        assert_eq!(game_result(&position), None);
+
+        position.set_halfmove_clock(150);
+        assert_eq!(game_result(&position), Some(GameResult::Draw(DrawReason::SeventyFiveMoveRule)));
+        assert!(!DrawReason::SeventyFiveMoveRule.is_claimable());
+    }
+
+    #[test]
+    fn checkmate_before_seventy_five_moves() {
+        let mut position = StandardPosition::starting_position();
+        for (from, to) in [("f2", "f3"), ("e7", "e5"), ("g2", "g4"), ("d8", "h4")] {
+            play(&mut position, from, to);
+        }
+        position.set_halfmove_clock(150);
+        assert_eq!(game_result(&position), Some(GameResult::BlackWins(WinReason::Checkmate)));
+    }
+
+    #[test]
+    fn insufficient_material() {
+        let mut position = StandardPosition::new(MailboxBoard::new(8, 8));
+        let board = position.board_mut();
+        board.set(Square::parse("e1").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::King, PieceColor::White))).unwrap();
+        board.set(Square::parse("e8").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::King, PieceColor::Black))).unwrap();
+        board.set(Square::parse("b1").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Knight, PieceColor::White))).unwrap();
+        assert_eq!(game_result(&position), Some(GameResult::Draw(DrawReason::InsufficientMaterial)));
+
+        position.board_mut().set(Square::parse("h2").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::White))).unwrap();
+        assert_eq!(game_result(&position), None);
+    }
+}
@@ -0,0 +1,394 @@
+use anyhow::{ensure, Context};
+use crate::board::Board;
+use crate::generator::MoveGenerator;
+use crate::square::Square;
+use crate::standard::generator::StandardMoveGenerator;
+use crate::standard::moves::StandardMove;
+use crate::standard::piece::{ColoredStandardPiece, StandardPiece};
+use crate::standard::position::{CastlingSide, StandardPosition};
+use crate::standard::result::is_checkmate;
+use crate::standard::validator::attack::is_in_check;
+use crate::standard::validator::{en_passant_capture_square, StandardMoveValidator};
+use crate::validator::MoveValidator;
+
+impl<B: Board<Token = ColoredStandardPiece> + Clone> StandardPosition<B> {
+    /// Writes a move in Standard Algebraic Notation, such as `Nbd7`, `exd5`, `O-O-O` or `e8=Q+`.
+    /// The origin of the move is only added when another piece of the same type could move to the same square.
+    ///
+    /// Returns Err(_) if the move is not legal in this position.
+    pub fn to_san(&self, mov: &StandardMove) -> anyhow::Result<String> {
+        let validator = StandardMoveValidator {};
+        ensure!(validator.validate(self, mov), "Move is not legal in the current position");
+        let piece = self.board().at(mov.from()).expect("Legal moves start on a piece");
+
+        let mut san = match self.castling_side(mov) {
+            Some(CastlingSide::KingSide) => "O-O".to_string(),
+            Some(CastlingSide::QueenSide) => "O-O-O".to_string(),
+            None => self.san_without_suffix(mov, piece.piece()),
+        };
+
+        let mut after = self.clone();
+        after.make_move(mov)?;
+        if is_checkmate(&after) {
+            san.push('#');
+        } else if is_in_check(after.board(), after.side_to_move()) {
+            san.push('+');
+        }
+
+        Ok(san)
+    }
+
+    // Writes everything except the check or checkmate marker, for any move that is not castling.
+    fn san_without_suffix(&self, mov: &StandardMove, piece: StandardPiece) -> String {
+        let san = self.write_san(mov, piece, false);
+        // Files have more than one letter on boards with more than 26 files, so the file the piece comes from can run
+        // into the destination (`Rab5`). If that makes the move ambiguous, the full starting square is written.
+        if self.board().width() > 26 && self.parse_san(&san).ok() != Some(*mov) {
+            return self.write_san(mov, piece, true);
+        }
+        san
+    }
+
+    fn write_san(&self, mov: &StandardMove, piece: StandardPiece, full_origin: bool) -> String {
+        let is_capture = self.board().at(mov.to()).is_some()
+            || en_passant_capture_square(self.board(), mov, self.en_passant()).is_some();
+
+        let mut san = String::new();
+        if piece != StandardPiece::Pawn {
+            san.push(piece.letter());
+        }
+        if full_origin {
+            san.push_str(&mov.from().to_string());
+        } else if piece != StandardPiece::Pawn {
+            san.push_str(&self.disambiguation(mov, piece));
+        } else if is_capture {
+            // Pawn captures are always written with the file the pawn came from.
+            san.push_str(&mov.from().file());
+        }
+
+        if is_capture {
+            san.push('x');
+        }
+        san.push_str(&mov.to().to_string());
+        if let Some(promotion) = mov.promotion() {
+            san.push('=');
+            san.push(promotion.letter());
+        }
+
+        san
+    }
+
+    // The shortest part of the starting square that tells this move apart from the moves of other pieces of the same
+    // type to the same square. Prefers the file over the rank, and only uses both if neither is enough on its own.
+    fn disambiguation(&self, mov: &StandardMove, piece: StandardPiece) -> String {
+        let generator = StandardMoveGenerator {};
+        let others: Vec<Square> = generator.generate(self)
+            .into_iter()
+            .filter(|other| other.to() == mov.to() && other.from() != mov.from())
+            .filter(|other| self.board().at(other.from()).is_some_and(|token| token.piece() == piece))
+            .map(|other| other.from())
+            .collect();
+
+        if others.is_empty() {
+            return String::new();
+        }
+
+        let file = mov.from().file();
+        let rank = mov.from().rank();
+        if others.iter().all(|other| other.column != mov.from().column) {
+            file
+        } else if others.iter().all(|other| other.row != mov.from().row) {
+            rank
+        } else {
+            format!("{file}{rank}")
+        }
+    }
+
+    /// Parses a move in Standard Algebraic Notation, and finds the matching legal move in this position.
+    /// Some common sloppy variants are accepted as well: castling with zeroes (`0-0`), superfluous or missing
+    /// disambiguation (`Nb1c3`), leaving out the capture marker, the `=` of a promotion or the check markers, and
+    /// trailing annotations such as `!?`.
+    ///
+    /// Returns Err(_) if the notation is malformed, or there is not exactly one legal move that matches it.
+    pub fn parse_san(&self, san: &str) -> anyhow::Result<StandardMove> {
+        let trimmed = san.trim().trim_end_matches(['+', '#', '!', '?']);
+        ensure!(!trimmed.is_empty(), "Empty move");
+
+        let castling = match trimmed {
+            "O-O" | "0-0" => Some(CastlingSide::KingSide),
+            "O-O-O" | "0-0-0" => Some(CastlingSide::QueenSide),
+            _ => None,
+        };
+        if let Some(side) = castling {
+            let mov = self.castling_move(self.side_to_move(), side)
+                .with_context(|| format!("Castling is not possible in the current position: '{san}'"))?;
+            let validator = StandardMoveValidator {};
+            ensure!(validator.validate(self, &mov), "Castling is not legal in the current position: '{san}'");
+            return Ok(mov);
+        }
+
+        let pattern = SanPattern::parse(trimmed).with_context(|| format!("Malformed move '{san}'"))?;
+        let generator = StandardMoveGenerator {};
+        let mut matching = generator.generate(self)
+            .into_iter()
+            .filter(|mov| pattern.matches(self, mov));
+        let mov = matching.next().with_context(|| format!("No legal move matches '{san}'"))?;
+        ensure!(matching.next().is_none(), "Move '{san}' is ambiguous");
+        Ok(mov)
+    }
+}
+
+// The parts of a (non-castling) move in SAN, with everything that was left out set to `None`.
+struct SanPattern {
+    piece: StandardPiece,
+    from_column: Option<u16>,
+    from_row: Option<u16>,
+    // The destination square as written. Files can have more than one letter, so when nothing separates the two,
+    // the file of the starting square can run into the destination (`Rab5` is a rook from the a-file to b5, or a
+    // rook to ab5). In that case `from_in_destination` is set, and both readings are tried.
+    destination: String,
+    from_in_destination: bool,
+    promotion: Option<StandardPiece>,
+}
+
+impl SanPattern {
+    fn parse(san: &str) -> anyhow::Result<Self> {
+        ensure!(san.is_ascii(), "Moves can only contain ascii characters");
+        let mut rest = san;
+
+        // Only uppercase letters are pieces, a lowercase `b` is always the b-file.
+        let mut piece = StandardPiece::Pawn;
+        if let Some(first) = rest.chars().next() && first.is_ascii_uppercase() {
+            piece = StandardPiece::from_letter(first).with_context(|| format!("Unknown piece letter '{first}'"))?;
+            rest = &rest[1..];
+        }
+
+        // The destination square always ends in a number, so a letter at the end must be a promotion.
+        let mut promotion = None;
+        if let Some(last) = rest.chars().last() && last.is_ascii_alphabetic() {
+            promotion = Some(StandardPiece::from_letter(last).with_context(|| format!("Unknown promotion piece '{last}'"))?);
+            rest = rest[..rest.len() - 1].trim_end_matches('=');
+        }
+
+        // The destination is the file letters followed by the rank number.
+        let rank_start = rest.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+        let mut file_start = rest[..rank_start].trim_end_matches(|c: char| c.is_ascii_lowercase()).len();
+        ensure!(file_start < rank_start && rank_start < rest.len(), "Missing destination square");
+        // An `x` in front of the last letter is the capture marker. Files starting with an `x` only exist on boards
+        // with hundreds of files, while x as the last letter can still be the x-file.
+        if let Some(capture) = rest[file_start..rank_start - 1].rfind('x') {
+            file_start += capture + 1;
+        }
+        // Checks the rank number, the file letters are checked against the legal moves.
+        Square::parse(&rest[rank_start - 1..])?;
+        let destination = rest[file_start..].to_string();
+        rest = &rest[..file_start];
+
+        // Whatever is left is the (partial) starting square, possibly with a capture marker.
+        let origin = rest.strip_suffix(['x', '-', ':']).unwrap_or(rest);
+        let mut from_column = None;
+        let mut from_row = None;
+        let digits = origin.trim_start_matches(|c: char| c.is_ascii_lowercase());
+        let letters = &origin[..origin.len() - digits.len()];
+        if !letters.is_empty() {
+            from_column = Some(Square::parse_file(letters).with_context(|| format!("Invalid starting square '{origin}'"))?);
+        }
+        if !digits.is_empty() {
+            let row: u16 = digits.parse().with_context(|| format!("Invalid starting square '{origin}'"))?;
+            ensure!(row >= 1, "Invalid starting square '{origin}'");
+            from_row = Some(row - 1);
+        }
+
+        Ok(Self { piece, from_column, from_row, destination, from_in_destination: rest.is_empty(), promotion })
+    }
+
+    fn matches<B: Board<Token = ColoredStandardPiece>>(&self, position: &StandardPosition<B>, mov: &StandardMove) -> bool {
+        let Some(token) = position.board().at(mov.from()) else { return false; };
+        let Some(from_file) = self.destination.strip_suffix(&mov.to().to_string()) else { return false; };
+        token.piece() == self.piece
+            && (from_file.is_empty() || self.from_in_destination && from_file == mov.from().file())
+            && mov.promotion() == self.promotion
+            && self.from_column.is_none_or(|column| column == mov.from().column)
+            && self.from_row.is_none_or(|row| row == mov.from().row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::mailbox::MailboxBoard;
+    use super::*;
+
+    fn mov(from: &str, to: &str) -> StandardMove {
+        StandardMove::new(Square::parse(from).unwrap(), Square::parse(to).unwrap())
+    }
+
+    fn fen(fen: &str) -> StandardPosition<MailboxBoard<ColoredStandardPiece>> {
+        StandardPosition::from_fen(fen).unwrap()
+    }
+
+    #[test]
+    fn simple_moves() {
+        let position = StandardPosition::starting_position();
+        assert_eq!(position.to_san(&mov("e2", "e4")).unwrap(), "e4");
+        assert_eq!(position.to_san(&mov("g1", "f3")).unwrap(), "Nf3");
+        assert!(position.to_san(&mov("e2", "e5")).is_err());
+
+        assert_eq!(position.parse_san("e4").unwrap(), mov("e2", "e4"));
+        assert_eq!(position.parse_san("Nf3").unwrap(), mov("g1", "f3"));
+        assert!(position.parse_san("e5").is_err());
+    }
+
+    #[test]
+    fn captures() {
+        let position = fen("rnbqkbnr/ppp1pppp/8/3p4/4P3/2N5/PPPP1PPP/R1BQKBNR w KQkq d6 0 2");
+        assert_eq!(position.to_san(&mov("e4", "d5")).unwrap(), "exd5");
+        assert_eq!(position.parse_san("exd5").unwrap(), mov("e4", "d5"));
+        assert_eq!(position.parse_san("ed5").unwrap(), mov("e4", "d5"));
+        assert_eq!(position.to_san(&mov("c3", "d5")).unwrap(), "Nxd5");
+
+        // En passant
+        let position = fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3");
+        assert_eq!(position.to_san(&mov("e5", "f6")).unwrap(), "exf6");
+        assert_eq!(position.parse_san("exf6").unwrap(), mov("e5", "f6"));
+    }
+
+    #[test]
+    fn disambiguation() {
+        // Knights on b8 and f6 can both go to d7, and rooks on a1 and a5 can both go to a3.
+        let position = fen("1n2k3/8/5n2/R7/8/8/8/R3K3 b - - 0 1");
+        assert_eq!(position.to_san(&mov("b8", "d7")).unwrap(), "Nbd7");
+        assert_eq!(position.to_san(&mov("f6", "d7")).unwrap(), "Nfd7");
+        assert_eq!(position.to_san(&mov("f6", "e4")).unwrap(), "Ne4");
+        assert!(position.parse_san("Nd7").is_err());
+        assert_eq!(position.parse_san("Nbd7").unwrap(), mov("b8", "d7"));
+
+        let position = fen("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1");
+        assert_eq!(position.to_san(&mov("a1", "a3")).unwrap(), "R1a3");
+        assert_eq!(position.to_san(&mov("a5", "a3")).unwrap(), "R5a3");
+        assert_eq!(position.parse_san("R5a3").unwrap(), mov("a5", "a3"));
+
+        // Three queens where neither file nor rank is enough
+        let position = fen("8/8/6k1/8/Q7/8/8/Q2QK3 w - - 0 1");
+        assert_eq!(position.to_san(&mov("a1", "d4")).unwrap(), "Qa1d4");
+        assert_eq!(position.to_san(&mov("a4", "d4")).unwrap(), "Q4d4");
+        assert_eq!(position.to_san(&mov("d1", "d4")).unwrap(), "Qdd4");
+        assert_eq!(position.parse_san("Qa1d4").unwrap(), mov("a1", "d4"));
+        assert!(position.parse_san("Qad4").is_err());
+
+        // A pinned piece does not need to be told apart
+        let position = fen("4k3/8/8/8/8/1N6/8/r2NK3 w - - 0 1");
+        assert_eq!(position.to_san(&mov("b3", "c5")).unwrap(), "Nc5");
+        assert_eq!(position.to_san(&mov("b3", "c1")).unwrap(), "Nc1");
+    }
+
+    #[test]
+    fn castling() {
+        let position = fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        let e1 = Square::parse("e1").unwrap();
+        assert_eq!(position.to_san(&StandardMove::castle(e1, CastlingSide::KingSide).unwrap()).unwrap(), "O-O");
+        assert_eq!(position.to_san(&StandardMove::castle(e1, CastlingSide::QueenSide).unwrap()).unwrap(), "O-O-O");
+        assert_eq!(position.parse_san("O-O").unwrap(), StandardMove::castle(e1, CastlingSide::KingSide).unwrap());
+        assert_eq!(position.parse_san("0-0-0").unwrap(), StandardMove::castle(e1, CastlingSide::QueenSide).unwrap());
+
+        let position = fen("r3k2r/8/8/8/8/8/8/R3K2R w - - 0 1");
+        assert!(position.parse_san("O-O").is_err());
+    }
+
+    #[test]
+    fn chess960_castling() {
+        // The king on b1 cannot move two squares to the queen side, but it can still castle to c1.
+        let position = fen("4k3/8/8/8/8/8/8/RK5R w A - 0 1");
+        let queen_side = StandardMove::new(Square::parse("b1").unwrap(), Square::parse("a1").unwrap());
+        assert_eq!(position.parse_san("O-O-O").unwrap(), queen_side);
+        assert_eq!(position.to_san(&queen_side).unwrap(), "O-O-O");
+        assert!(position.parse_san("O-O").is_err());
+
+        let position = fen("4k3/8/8/8/8/8/8/R5KR w H - 0 1");
+        let king_side = StandardMove::new(Square::parse("g1").unwrap(), Square::parse("h1").unwrap());
+        assert_eq!(position.parse_san("O-O").unwrap(), king_side);
+        assert_eq!(position.to_san(&king_side).unwrap(), "O-O");
+        assert!(position.parse_san("O-O-O").is_err());
+    }
+
+    #[test]
+    fn promotion_and_check() {
+        let position = fen("3k4/4P3/8/8/8/8/8/4K3 w - - 0 1");
+        let e7 = Square::parse("e7").unwrap();
+        let e8 = Square::parse("e8").unwrap();
+        assert_eq!(position.to_san(&StandardMove::promote(e7, e8, StandardPiece::Queen)).unwrap(), "e8=Q+");
+        assert_eq!(position.to_san(&StandardMove::promote(e7, e8, StandardPiece::Knight)).unwrap(), "e8=N");
+        assert_eq!(position.parse_san("e8=Q+").unwrap(), StandardMove::promote(e7, e8, StandardPiece::Queen));
+        assert_eq!(position.parse_san("e8R").unwrap(), StandardMove::promote(e7, e8, StandardPiece::Rook));
+        assert_eq!(position.parse_san("e8=b").unwrap(), StandardMove::promote(e7, e8, StandardPiece::Bishop));
+        assert!(position.parse_san("e8").is_err());
+
+        // Fool's mate
+        let position = fen("rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2");
+        assert_eq!(position.to_san(&mov("d8", "h4")).unwrap(), "Qh4#");
+        assert_eq!(position.parse_san("Qh4#").unwrap(), mov("d8", "h4"));
+    }
+
+    #[test]
+    fn sloppy_input() {
+        let position = StandardPosition::starting_position();
+        assert_eq!(position.parse_san("Nb1c3").unwrap(), mov("b1", "c3"));
+        assert_eq!(position.parse_san("Nb1-c3").unwrap(), mov("b1", "c3"));
+        assert_eq!(position.parse_san("e2e4").unwrap(), mov("e2", "e4"));
+        assert_eq!(position.parse_san("e4!?").unwrap(), mov("e2", "e4"));
+
+        assert!(position.parse_san("").is_err());
+        assert!(position.parse_san("Xe4").is_err());
+        assert!(position.parse_san("N").is_err());
+        assert!(position.parse_san("Nc9").is_err());
+    }
+
+    #[test]
+    fn wide_board() {
+        // Files past z have two letters, so the rook going from ab1 to ab5 could also be read as a rook from the
+        // a-file to b5.
+        let position = fen("2k25/28/28/28/28/28/28/26KR w - - 0 1");
+        assert_eq!(position.to_san(&mov("ab1", "ab5")).unwrap(), "Rab5");
+        assert_eq!(position.parse_san("Rab5").unwrap(), mov("ab1", "ab5"));
+
+        // With a rook on a5 both readings are legal. The starting file can have more than one letter as well.
+        let position = fen("2k25/28/28/R27/28/28/28/26KR w - - 0 1");
+        assert!(position.parse_san("Rab5").is_err());
+        assert_eq!(position.to_san(&mov("ab1", "ab5")).unwrap(), "Rabab5");
+        assert_eq!(position.parse_san("Rabab5").unwrap(), mov("ab1", "ab5"));
+        assert_eq!(position.to_san(&mov("a5", "ab5")).unwrap(), "Raab5");
+        assert_eq!(position.parse_san("Raab5").unwrap(), mov("a5", "ab5"));
+        assert_eq!(position.parse_san("Rab1ab5").unwrap(), mov("ab1", "ab5"));
+        assert_eq!(position.to_san(&mov("a5", "b5")).unwrap(), "Rb5");
+
+        let position = fen("2k25/28/28/28/28/28/8p19/R26K b - - 0 1");
+        let promotion = StandardMove::promote(Square::parse("i2").unwrap(), Square::parse("i1").unwrap(), StandardPiece::Queen);
+        assert_eq!(position.to_san(&promotion).unwrap(), "i1=Q+");
+        assert_eq!(position.parse_san("i1=Q+").unwrap(), promotion);
+
+        // The x-file next to a capture marker
+        let position = fen("2k25/28/28/23ppp2/24P3/28/28/27K b - - 0 1");
+        assert_eq!(position.to_san(&mov("x5", "y4")).unwrap(), "xxy4");
+        assert_eq!(position.parse_san("xxy4").unwrap(), mov("x5", "y4"));
+        assert_eq!(position.parse_san("x4").unwrap(), mov("x5", "x4"));
+
+        let generator = StandardMoveGenerator {};
+        for fen in ["2k25/28/28/1n26/R27/28/5p22/R25QK b - - 0 1", "2k25/28/28/1n26/R27/28/28/R25QK w - - 0 1",
+                    "2k1r23/28/28/23ppp2/24P3/28/28/R26K b - - 0 1"] {
+            let position = self::fen(fen);
+            for mov in generator.generate(&position) {
+                let san = position.to_san(&mov).unwrap();
+                assert_eq!(position.parse_san(&san).unwrap(), mov, "{san}");
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let generator = StandardMoveGenerator {};
+        let position = fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        for mov in generator.generate(&position) {
+            let san = position.to_san(&mov).unwrap();
+            assert_eq!(position.parse_san(&san).unwrap(), mov, "{san}");
+        }
+    }
+}
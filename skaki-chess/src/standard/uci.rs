@@ -0,0 +1,104 @@
+use anyhow::Context;
+use crate::board::Board;
+use crate::standard::moves::StandardMove;
+use crate::standard::piece::{ColoredStandardPiece, StandardPiece};
+use crate::standard::position::{CastlingSide, StandardPosition};
+
+impl<B: Board<Token = ColoredStandardPiece>> StandardPosition<B> {
+    /// Writes a move in UCI notation. Regular chess engines expect castling as the move of the king (`e1g1`), while
+    /// in Chess960 mode castling is written as the king capturing its own rook (`e1h1`). When the king moves less
+    /// than two squares, castling is written as the king capturing its rook in both modes.
+    pub fn to_uci(&self, mov: &StandardMove, chess960: bool) -> String {
+        match self.castling_rook_squares(mov) {
+            Some((rook, _)) if chess960 => StandardMove::new(mov.from(), rook).to_string(),
+            _ => mov.to_string(),
+        }
+    }
+
+    /// Parses a move in UCI notation. Castling is accepted both as the move of the king (`e1g1`) and as the king
+    /// capturing its own rook (`e1h1`), which is turned into the castling move of the position. In Chess960, the
+    /// king and rook then end up on the same squares as in a regular game.
+    /// This does not check whether the move is legal.
+    pub fn parse_uci(&self, uci: &str) -> anyhow::Result<StandardMove> {
+        let mov: StandardMove = uci.parse()?;
+        let board = self.board();
+        let Some(king) = board.at(mov.from()).filter(|piece| piece.piece() == StandardPiece::King) else { return Ok(mov); };
+        let target = board.at(mov.to());
+        if mov.promotion().is_some() || target != Some(ColoredStandardPiece::new(StandardPiece::Rook, king.color())) {
+            return Ok(mov);
+        }
+
+        // The king lands on its own rook, which is only possible when castling with that rook.
+        let side = [CastlingSide::KingSide, CastlingSide::QueenSide]
+            .into_iter()
+            .find(|&side| self.castling_rights().rook_column(king.color(), side) == Some(mov.to().column) && mov.to().row == mov.from().row)
+            .with_context(|| format!("UCI move '{uci}' moves the king onto its own rook, but it cannot castle with that rook"))?;
+        self.castling_move(king.color(), side)
+            .filter(|castling| castling.from() == mov.from())
+            .with_context(|| format!("UCI move '{uci}' moves the king onto its own rook, but the king cannot castle from there"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::square::Square;
+    use crate::standard::piece::PieceColor;
+    use super::*;
+
+    #[test]
+    fn castling() {
+        let position = StandardPosition::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let e1 = Square::parse("e1").unwrap();
+        let king_side = StandardMove::castle(e1, CastlingSide::KingSide).unwrap();
+        let queen_side = StandardMove::castle(e1, CastlingSide::QueenSide).unwrap();
+
+        assert_eq!(position.to_uci(&king_side, false), "e1g1");
+        assert_eq!(position.to_uci(&queen_side, false), "e1c1");
+        assert_eq!(position.to_uci(&king_side, true), "e1h1");
+        assert_eq!(position.to_uci(&queen_side, true), "e1a1");
+
+        for (uci, expected) in [("e1g1", king_side), ("e1h1", king_side), ("e1c1", queen_side), ("e1a1", queen_side)] {
+            assert_eq!(position.parse_uci(uci).unwrap(), expected);
+        }
+
+        // Other moves are not changed
+        let mov = StandardMove::new(Square::parse("a1").unwrap(), Square::parse("a8").unwrap());
+        assert_eq!(position.to_uci(&mov, true), "a1a8");
+        assert_eq!(position.parse_uci("a1a8").unwrap(), mov);
+    }
+
+    #[test]
+    fn chess960_castling() {
+        let uci = |position: &StandardPosition<_>, uci: &str| position.parse_uci(uci).unwrap();
+        let mov = |from: &str, to: &str| StandardMove::new(Square::parse(from).unwrap(), Square::parse(to).unwrap());
+
+        // With the king on b1, castling queen side moves the king one square, so it is always written as b1a1.
+        let position = StandardPosition::from_fen("4k3/8/8/8/8/8/8/RK5R w HA - 0 1").unwrap();
+        assert_eq!(uci(&position, "b1a1"), mov("b1", "a1"));
+        assert_eq!(position.to_uci(&mov("b1", "a1"), true), "b1a1");
+        assert_eq!(position.to_uci(&mov("b1", "a1"), false), "b1a1");
+        assert_eq!(uci(&position, "b1h1"), mov("b1", "g1"));
+        assert_eq!(position.to_uci(&mov("b1", "g1"), true), "b1h1");
+        assert_eq!(position.to_uci(&mov("b1", "g1"), false), "b1g1");
+
+        // With the king on g1, it stays in place when castling king side.
+        let mut position = StandardPosition::from_fen("4k3/8/8/8/8/8/8/1R4KR w HB - 0 1").unwrap();
+        assert_eq!(uci(&position, "g1h1"), mov("g1", "h1"));
+        assert_eq!(uci(&position, "g1b1"), mov("g1", "c1"));
+        assert_eq!(position.to_uci(&mov("g1", "c1"), true), "g1b1");
+
+        position.make_move(&uci(&position, "g1h1")).unwrap();
+        assert_eq!(position.board().at(Square::parse("g1").unwrap()), Some(ColoredStandardPiece::new(StandardPiece::King, PieceColor::White)));
+        assert_eq!(position.board().at(Square::parse("f1").unwrap()), Some(ColoredStandardPiece::new(StandardPiece::Rook, PieceColor::White)));
+        assert_eq!(position.board().at(Square::parse("h1").unwrap()), None);
+    }
+
+    #[test]
+    fn king_onto_rook_without_rights() {
+        let position = StandardPosition::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w Qkq - 0 1").unwrap();
+        assert!(position.parse_uci("e1h1").is_err());
+        assert!(position.parse_uci("e1a1").is_ok());
+        assert!(position.parse_uci("e1e9").is_ok());
+        assert!(position.parse_uci("e1").is_err());
+    }
+}
@@ -0,0 +1,109 @@
+use crate::board::Board;
+use crate::square::Square;
+use crate::standard::piece::{ColoredStandardPiece, PieceColor, StandardPiece};
+use crate::standard::validator::piece_move::{is_bishop_move_illegal, is_king_move_illegal, is_knight_move_illegal, is_path_obstructed, is_pawn_move_illegal, is_queen_move_illegal, is_rook_move_illegal};
+
+/// Returns true if the piece on `from` attacks the square `target`, meaning it could capture a piece on that square.
+/// Unlike a regular move, this does not care about what is on the target square.
+pub fn piece_attacks<B: Board<Token = ColoredStandardPiece>>(piece: &ColoredStandardPiece, board: &B, from: Square, target: Square) -> bool {
+    // A piece never attacks its own square.
+    if from == target {
+        return false;
+    }
+
+    match piece.piece() {
+        // Pawns only attack diagonally, never straight ahead.
+        StandardPiece::Pawn => {
+            from.column != target.column && !is_pawn_move_illegal(piece.color(), board.height(), from, target)
+        }
+        StandardPiece::Knight => !is_knight_move_illegal(from, target),
+        StandardPiece::Bishop => !is_bishop_move_illegal(from, target) && !is_path_obstructed(board, from, target),
+        StandardPiece::Rook => !is_rook_move_illegal(from, target) && !is_path_obstructed(board, from, target),
+        StandardPiece::Queen => !is_queen_move_illegal(from, target) && !is_path_obstructed(board, from, target),
+        StandardPiece::King => !is_king_move_illegal(from, target),
+    }
+}
+
+/// Returns true if any piece of color `by` attacks the given square.
+pub fn is_square_attacked<B: Board<Token = ColoredStandardPiece>>(board: &B, square: Square, by: PieceColor) -> bool {
+    board.iter().any(|(from, piece)| piece.color() == by && piece_attacks(piece, board, from, square))
+}
+
+/// Finds the square of the king of the given color.
+/// Returns `None` if there is no such king on the board.
+pub fn find_king<B: Board<Token = ColoredStandardPiece>>(board: &B, color: PieceColor) -> Option<Square> {
+    let king = ColoredStandardPiece::new(StandardPiece::King, color);
+    board.find(|token| *token == king)
+}
+
+/// Returns true if the king of the given color is currently attacked by the opponent.
+/// A player without a king can never be in check.
+pub fn is_in_check<B: Board<Token = ColoredStandardPiece>>(board: &B, color: PieceColor) -> bool {
+    match find_king(board, color) {
+        Some(king) => is_square_attacked(board, king, color.opposite()),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::mailbox::MailboxBoard;
+    use super::*;
+
+    fn place(board: &mut MailboxBoard<ColoredStandardPiece>, square: &str, piece: StandardPiece, color: PieceColor) {
+        board.set(Square::parse(square).unwrap(), Some(ColoredStandardPiece::new(piece, color))).unwrap();
+    }
+
+    #[test]
+    fn pawn_attacks() {
+        let mut board = MailboxBoard::new(8, 8);
+        place(&mut board, "e4", StandardPiece::Pawn, PieceColor::White);
+        place(&mut board, "c6", StandardPiece::Pawn, PieceColor::Black);
+
+        assert!(is_square_attacked(&board, Square::parse("d5").unwrap(), PieceColor::White));
+        assert!(is_square_attacked(&board, Square::parse("f5").unwrap(), PieceColor::White));
+        // Pawns do not attack the square in front of them, or backward
+        assert!(!is_square_attacked(&board, Square::parse("e5").unwrap(), PieceColor::White));
+        assert!(!is_square_attacked(&board, Square::parse("d3").unwrap(), PieceColor::White));
+
+        assert!(is_square_attacked(&board, Square::parse("d5").unwrap(), PieceColor::Black));
+        assert!(is_square_attacked(&board, Square::parse("b5").unwrap(), PieceColor::Black));
+        assert!(!is_square_attacked(&board, Square::parse("d7").unwrap(), PieceColor::Black));
+    }
+
+    #[test]
+    fn sliding_attacks_are_blocked() {
+        let mut board = MailboxBoard::new(8, 8);
+        place(&mut board, "a1", StandardPiece::Rook, PieceColor::White);
+        place(&mut board, "a4", StandardPiece::Knight, PieceColor::Black);
+        place(&mut board, "h8", StandardPiece::Bishop, PieceColor::Black);
+        place(&mut board, "e5", StandardPiece::Pawn, PieceColor::White);
+
+        // The rook attacks the knight, but not the squares behind it
+        assert!(is_square_attacked(&board, Square::parse("a4").unwrap(), PieceColor::White));
+        assert!(!is_square_attacked(&board, Square::parse("a5").unwrap(), PieceColor::White));
+        assert!(is_square_attacked(&board, Square::parse("h1").unwrap(), PieceColor::White));
+        // The bishop is blocked by the pawn on e5
+        assert!(is_square_attacked(&board, Square::parse("e5").unwrap(), PieceColor::Black));
+        assert!(!is_square_attacked(&board, Square::parse("d4").unwrap(), PieceColor::Black));
+    }
+
+    #[test]
+    fn check_detection() {
+        let mut board = MailboxBoard::new(8, 8);
+        place(&mut board, "e1", StandardPiece::King, PieceColor::White);
+        place(&mut board, "e8", StandardPiece::King, PieceColor::Black);
+        place(&mut board, "b4", StandardPiece::Bishop, PieceColor::Black);
+        assert_eq!(find_king(&board, PieceColor::White), Some(Square::parse("e1").unwrap()));
+        assert!(is_in_check(&board, PieceColor::White));
+        assert!(!is_in_check(&board, PieceColor::Black));
+
+        // Blocking the diagonal resolves the check
+        place(&mut board, "d2", StandardPiece::Knight, PieceColor::White);
+        assert!(!is_in_check(&board, PieceColor::White));
+
+        // Without a king, there is no check
+        let board = MailboxBoard::<ColoredStandardPiece>::new(8, 8);
+        assert!(!is_in_check(&board, PieceColor::White));
+    }
+}
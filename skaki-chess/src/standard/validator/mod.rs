@@ -1,9 +1,13 @@
 mod piece_move;
+pub mod attack;
 
 use crate::board::Board;
 use crate::standard::moves::StandardMove;
-use crate::standard::piece::ColoredStandardPiece;
-use crate::standard::validator::piece_move::is_movement_illegal;
+use crate::square::Square;
+use crate::standard::piece::{ColoredStandardPiece, StandardPiece};
+use crate::standard::position::{CastlingSide, StandardPosition};
+use crate::standard::validator::attack::{is_in_check, is_square_attacked};
+use crate::standard::validator::piece_move::{is_movement_illegal, is_path_obstructed, promotion_row};
 use crate::validator::MoveValidator;
 
 /// The standard move validator, implements the basic chess rules.
@@ -14,25 +18,163 @@ pub struct StandardMoveValidator {
 
 // A standard game can be played on any board that uses the standard piece set as tokens, and is played using
 // standard moves. This move validator implements the ruleset for these games.
-impl<B: Board<Token = ColoredStandardPiece>> MoveValidator<B, StandardMove> for StandardMoveValidator {
+impl<B: Board<Token = ColoredStandardPiece> + Clone> MoveValidator<B, StandardMove> for StandardMoveValidator {
     fn validate(&self, board: &B, mov: &StandardMove) -> bool {
-        // An empty move is not valid, you cannot move an empty square.
-        if let Some(piece) = board.at(mov.from()) {
-            // You cannot move from a square to the same square
-            if mov.from() == mov.to() { return false; }
-            
-            // Simple check if the movement itself is legal.
-            // If it isn't, we can skip any complicated checks.
-            if is_movement_illegal(piece, board, mov.from(), mov.to()) {
+        // A bare board does not know the previous move, so en passant is never possible.
+        is_board_move_legal(board, mov, None)
+    }
+}
+
+// Validates everything about a move that only depends on the board, and the en passant square if there is one.
+fn is_board_move_legal<B: Board<Token = ColoredStandardPiece> + Clone>(board: &B, mov: &StandardMove, en_passant: Option<Square>) -> bool {
+    // An empty move is not valid, you cannot move an empty square.
+    if let Some(piece) = board.at(mov.from()) {
+        // You cannot move from a square to the same square
+        if mov.from() == mov.to() { return false; }
+
+        // Simple check if the movement itself is legal.
+        // If it isn't, we can skip any complicated checks.
+        if is_movement_illegal(piece.clone(), board, mov.from(), mov.to()) {
+            return false;
+        }
+
+        // Apart from knights, no piece can jump over other pieces. This covers sliding pieces and
+        // pawns making a double step.
+        if piece.piece() != StandardPiece::Knight && is_path_obstructed(board, mov.from(), mov.to()) {
+            return false;
+        }
+
+        // A piece can never land on a piece of its own color.
+        let target = board.at(mov.to());
+        if target.as_ref().is_some_and(|target| target.color() == piece.color()) {
+            return false;
+        }
+
+        // Pawns are the only piece that capture differently from how they move.
+        // Moving straight forward requires an empty square, while a diagonal step is only allowed as a capture.
+        // The one exception is en passant, where the captured pawn is next to the pawn instead of on the destination.
+        let mut en_passant_capture = None;
+        if piece.piece() == StandardPiece::Pawn {
+            let is_capture = mov.from().column != mov.to().column;
+            if is_capture && target.is_none() {
+                en_passant_capture = en_passant_capture_square(board, mov, en_passant);
+                if en_passant_capture.is_none() {
+                    return false;
+                }
+            } else if is_capture != target.is_some() {
                 return false;
-            } else {
-                // TODO: We have verified that the basic movement is allowed. Now we need to check everything else.
-                return true;
             }
         }
 
-        false
+        // A pawn reaching the last row has to promote, and other moves can never promote.
+        let must_promote = piece.piece() == StandardPiece::Pawn && mov.to().row == promotion_row(piece.color(), board.height());
+        match mov.promotion() {
+            // Promoting to a pawn or king is not allowed.
+            Some(StandardPiece::Pawn | StandardPiece::King) => return false,
+            Some(_) if !must_promote => return false,
+            None if must_promote => return false,
+            _ => {}
+        }
+
+        // Finally, a move is never allowed to leave the king of the moving player in check.
+        // This covers moving the king into check, moving pinned pieces and ignoring an existing check.
+        return !leaves_king_in_check(board, mov, piece, en_passant_capture);
+    }
+
+    false
+}
+
+/// If the move is an en passant capture, returns the square of the pawn that is captured.
+/// This is the square the enemy pawn moved to with its double step, right next to the starting square of the move.
+pub fn en_passant_capture_square<B: Board<Token = ColoredStandardPiece>>(board: &B, mov: &StandardMove, en_passant: Option<Square>) -> Option<Square> {
+    let piece = board.at(mov.from())?;
+    if piece.piece() != StandardPiece::Pawn || en_passant != Some(mov.to()) || mov.from().column == mov.to().column {
+        return None;
+    }
+
+    // The pawn that made the double step must still be there.
+    let captured = Square::new(mov.from().row, mov.to().column);
+    let enemy_pawn = ColoredStandardPiece::new(StandardPiece::Pawn, piece.color().opposite());
+    (board.at(captured) == Some(enemy_pawn)).then_some(captured)
+}
+
+// Plays the move on a copy of the board, and checks whether the king of the moving player is attacked afterward.
+// Next to the destination square, a move can capture on one other square (for en passant), which is cleared as well.
+fn leaves_king_in_check<B: Board<Token = ColoredStandardPiece> + Clone>(board: &B, mov: &StandardMove, piece: ColoredStandardPiece, captured: Option<Square>) -> bool {
+    let color = piece.color();
+    let mut after = board.clone();
+    if after.set(mov.to(), Some(piece)).is_err() || after.set(mov.from(), None).is_err() {
+        // Moves that leave the board are never legal in the first place.
+        return true;
+    }
+    if let Some(captured) = captured && after.set(captured, None).is_err() {
+        return true;
+    }
+
+    is_in_check(&after, color)
+}
+
+// Validating against a full position additionally takes into account whose turn it is.
+impl<B: Board<Token = ColoredStandardPiece> + Clone> MoveValidator<StandardPosition<B>, StandardMove> for StandardMoveValidator {
+    fn validate(&self, position: &StandardPosition<B>, mov: &StandardMove) -> bool {
+        // Only the side to move can make a move.
+        let Some(piece) = position.board().at(mov.from()) else { return false; };
+        if piece.color() != position.side_to_move() {
+            return false;
+        }
+
+        // Castling is the only move that needs the castling rights, so it is validated separately.
+        if let Some(side) = position.castling_side(mov) {
+            return is_castling_legal(position, mov, side);
+        }
+
+        is_board_move_legal(position.board(), mov, position.en_passant())
+    }
+}
+
+// Checks all preconditions for castling. The king cannot have moved, and the rook must still be in place.
+// All squares between the king and rook and their destinations must be empty, and the king cannot castle out of,
+// through or into check.
+fn is_castling_legal<B: Board<Token = ColoredStandardPiece> + Clone>(position: &StandardPosition<B>, mov: &StandardMove, side: CastlingSide) -> bool {
+    let board = position.board();
+    let color = position.side_to_move();
+    let row = mov.from().row;
+    // If there is no rook to castle with, castling rights were lost.
+    let Some((rook_from, rook_to)) = position.castling_rook_squares(mov) else { return false; };
+    let Some(king_to) = position.castling_king_square(mov) else { return false; };
+    if board.at(rook_from) != Some(ColoredStandardPiece::new(StandardPiece::Rook, color)) {
+        return false;
+    }
+
+    // The rook has to be on the side the king is castling to.
+    let rook_on_side = match side {
+        CastlingSide::KingSide => rook_from.column > mov.from().column,
+        CastlingSide::QueenSide => rook_from.column < mov.from().column,
+    };
+    if !rook_on_side {
+        return false;
+    }
+
+    // Every square either piece touches must be empty, except for the squares of the king and rook themselves.
+    let columns = [mov.from().column, king_to.column, rook_from.column, rook_to.column];
+    let min = *columns.iter().min().expect("Not empty");
+    let max = *columns.iter().max().expect("Not empty");
+    for column in min..=max {
+        let square = Square::new(row, column);
+        if square != mov.from() && square != rook_from && board.get(square).is_some() {
+            return false;
+        }
+    }
+
+    // The king cannot be in check, pass through an attacked square or end up in check. The rook is lifted off the
+    // board first, since in Chess960 it can stand between the king's destination and an enemy piece on the back rank.
+    let mut without_rook = board.clone();
+    if without_rook.set(rook_from, None).is_err() {
+        return false;
     }
+    let start = mov.from().column.min(king_to.column);
+    let end = mov.from().column.max(king_to.column);
+    !(start..=end).any(|column| is_square_attacked(&without_rook, Square::new(row, column), color.opposite()))
 }
 
 #[cfg(test)]
@@ -56,4 +198,330 @@ mod tests {
         let validator = StandardMoveValidator {};
         assert!(validator.validate(&board, &mov));
     }
+
+    #[test]
+    fn sliding_pieces_cannot_jump() {
+        let mut board = MailboxBoard::<ColoredStandardPiece>::new(8, 8);
+        let validator = StandardMoveValidator {};
+        board.set(Square::parse("a1").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Rook, PieceColor::White))).unwrap();
+        board.set(Square::parse("a2").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::White))).unwrap();
+        board.set(Square::parse("c1").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Bishop, PieceColor::White))).unwrap();
+        board.set(Square::parse("d2").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::White))).unwrap();
+        board.set(Square::parse("d1").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Queen, PieceColor::White))).unwrap();
+        board.set(Square::parse("b1").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Knight, PieceColor::White))).unwrap();
+
+        // The rook cannot move through the pawn on a2
+        assert!(!validator.validate(&board, &StandardMove::new(Square::parse("a1").unwrap(), Square::parse("a8").unwrap())));
+        // The bishop cannot move through the pawn on d2
+        assert!(!validator.validate(&board, &StandardMove::new(Square::parse("c1").unwrap(), Square::parse("g5").unwrap())));
+        // The queen is blocked along the file, but not along the diagonal
+        assert!(!validator.validate(&board, &StandardMove::new(Square::parse("d1").unwrap(), Square::parse("d5").unwrap())));
+        assert!(validator.validate(&board, &StandardMove::new(Square::parse("d1").unwrap(), Square::parse("g4").unwrap())));
+        // The knight can jump over the pawns
+        assert!(validator.validate(&board, &StandardMove::new(Square::parse("b1").unwrap(), Square::parse("c3").unwrap())));
+    }
+
+    #[test]
+    fn pawn_double_step_cannot_jump() {
+        let mut board = MailboxBoard::<ColoredStandardPiece>::new(8, 8);
+        let validator = StandardMoveValidator {};
+        board.set(Square::parse("e2").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::White))).unwrap();
+        board.set(Square::parse("e3").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Knight, PieceColor::Black))).unwrap();
+        board.set(Square::parse("d7").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::Black))).unwrap();
+
+        assert!(!validator.validate(&board, &StandardMove::new(Square::parse("e2").unwrap(), Square::parse("e4").unwrap())));
+        assert!(validator.validate(&board, &StandardMove::new(Square::parse("d7").unwrap(), Square::parse("d5").unwrap())));
+    }
+
+    #[test]
+    fn no_friendly_fire() {
+        let mut board = MailboxBoard::<ColoredStandardPiece>::new(8, 8);
+        let validator = StandardMoveValidator {};
+        board.set(Square::parse("g1").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Knight, PieceColor::White))).unwrap();
+        board.set(Square::parse("e2").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::White))).unwrap();
+        board.set(Square::parse("h3").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::Black))).unwrap();
+        board.set(Square::parse("h8").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Rook, PieceColor::Black))).unwrap();
+
+        // The knight cannot land on its own pawn, but can capture the enemy pawn
+        assert!(!validator.validate(&board, &StandardMove::new(Square::parse("g1").unwrap(), Square::parse("e2").unwrap())));
+        assert!(validator.validate(&board, &StandardMove::new(Square::parse("g1").unwrap(), Square::parse("h3").unwrap())));
+        // Same for black
+        assert!(!validator.validate(&board, &StandardMove::new(Square::parse("h8").unwrap(), Square::parse("h3").unwrap())));
+        assert!(validator.validate(&board, &StandardMove::new(Square::parse("h3").unwrap(), Square::parse("h2").unwrap())));
+    }
+
+    #[test]
+    fn pawn_captures() {
+        let mut board = MailboxBoard::<ColoredStandardPiece>::new(8, 8);
+        let validator = StandardMoveValidator {};
+        board.set(Square::parse("e4").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::White))).unwrap();
+        board.set(Square::parse("e5").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::Black))).unwrap();
+        board.set(Square::parse("d5").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Knight, PieceColor::Black))).unwrap();
+        board.set(Square::parse("b2").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::White))).unwrap();
+        board.set(Square::parse("b4").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Bishop, PieceColor::Black))).unwrap();
+
+        // Pawns cannot capture straight ahead
+        assert!(!validator.validate(&board, &StandardMove::new(Square::parse("e4").unwrap(), Square::parse("e5").unwrap())));
+        assert!(!validator.validate(&board, &StandardMove::new(Square::parse("e5").unwrap(), Square::parse("e4").unwrap())));
+        // Not even with a double step
+        assert!(!validator.validate(&board, &StandardMove::new(Square::parse("b2").unwrap(), Square::parse("b4").unwrap())));
+        // Diagonal steps are captures, and need an enemy piece on the destination
+        assert!(validator.validate(&board, &StandardMove::new(Square::parse("e4").unwrap(), Square::parse("d5").unwrap())));
+        assert!(!validator.validate(&board, &StandardMove::new(Square::parse("e4").unwrap(), Square::parse("f5").unwrap())));
+        assert!(!validator.validate(&board, &StandardMove::new(Square::parse("b2").unwrap(), Square::parse("a3").unwrap())));
+        // Regular pawn steps are still allowed
+        assert!(validator.validate(&board, &StandardMove::new(Square::parse("b2").unwrap(), Square::parse("b3").unwrap())));
+    }
+
+    #[test]
+    fn king_moves() {
+        let mut board = MailboxBoard::<ColoredStandardPiece>::new(8, 8);
+        let validator = StandardMoveValidator {};
+        board.set(Square::parse("e1").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::King, PieceColor::White))).unwrap();
+        board.set(Square::parse("d8").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Rook, PieceColor::Black))).unwrap();
+        board.set(Square::parse("f3").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::Black))).unwrap();
+        board.set(Square::parse("f2").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::Black))).unwrap();
+        board.set(Square::parse("g3").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::Black))).unwrap();
+
+        // Kings move a single step
+        assert!(validator.validate(&board, &StandardMove::new(Square::parse("e1").unwrap(), Square::parse("f1").unwrap())));
+        assert!(!validator.validate(&board, &StandardMove::new(Square::parse("e1").unwrap(), Square::parse("e3").unwrap())));
+        // But cannot step into an attacked square
+        assert!(!validator.validate(&board, &StandardMove::new(Square::parse("e1").unwrap(), Square::parse("e2").unwrap())));
+        assert!(!validator.validate(&board, &StandardMove::new(Square::parse("e1").unwrap(), Square::parse("d1").unwrap())));
+        assert!(!validator.validate(&board, &StandardMove::new(Square::parse("e1").unwrap(), Square::parse("d2").unwrap())));
+        // Capturing a protected piece is not allowed either
+        assert!(!validator.validate(&board, &StandardMove::new(Square::parse("e1").unwrap(), Square::parse("f2").unwrap())));
+        board.set(Square::parse("g3").unwrap(), None).unwrap();
+        assert!(validator.validate(&board, &StandardMove::new(Square::parse("e1").unwrap(), Square::parse("f2").unwrap())));
+    }
+
+    #[test]
+    fn pinned_pieces() {
+        let mut board = MailboxBoard::<ColoredStandardPiece>::new(8, 8);
+        let validator = StandardMoveValidator {};
+        board.set(Square::parse("e1").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::King, PieceColor::White))).unwrap();
+        board.set(Square::parse("e3").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Rook, PieceColor::White))).unwrap();
+        board.set(Square::parse("d2").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Knight, PieceColor::White))).unwrap();
+        board.set(Square::parse("e8").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Rook, PieceColor::Black))).unwrap();
+        board.set(Square::parse("a5").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Queen, PieceColor::Black))).unwrap();
+
+        // The knight is pinned by the queen, and cannot move at all
+        assert!(!validator.validate(&board, &StandardMove::new(Square::parse("d2").unwrap(), Square::parse("f3").unwrap())));
+        // The rook is pinned along the file, but can still move along it
+        assert!(!validator.validate(&board, &StandardMove::new(Square::parse("e3").unwrap(), Square::parse("a3").unwrap())));
+        assert!(validator.validate(&board, &StandardMove::new(Square::parse("e3").unwrap(), Square::parse("e5").unwrap())));
+        assert!(validator.validate(&board, &StandardMove::new(Square::parse("e3").unwrap(), Square::parse("e8").unwrap())));
+    }
+
+    #[test]
+    fn resolve_check() {
+        let mut board = MailboxBoard::<ColoredStandardPiece>::new(8, 8);
+        let validator = StandardMoveValidator {};
+        board.set(Square::parse("g8").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::King, PieceColor::Black))).unwrap();
+        board.set(Square::parse("h7").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::Black))).unwrap();
+        board.set(Square::parse("b6").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Knight, PieceColor::Black))).unwrap();
+        board.set(Square::parse("a8").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Rook, PieceColor::White))).unwrap();
+
+        // Black is in check along the back rank. Unrelated moves are not allowed.
+        assert!(!validator.validate(&board, &StandardMove::new(Square::parse("h7").unwrap(), Square::parse("h6").unwrap())));
+        // Moving the king out of the way
+        assert!(validator.validate(&board, &StandardMove::new(Square::parse("g8").unwrap(), Square::parse("g7").unwrap())));
+        // Capturing the checking piece
+        assert!(validator.validate(&board, &StandardMove::new(Square::parse("b6").unwrap(), Square::parse("a8").unwrap())));
+        // Once a bishop blocks the check, moving it away would expose the king again
+        board.set(Square::parse("c8").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Bishop, PieceColor::Black))).unwrap();
+        assert!(validator.validate(&board, &StandardMove::new(Square::parse("h7").unwrap(), Square::parse("h6").unwrap())));
+        assert!(!validator.validate(&board, &StandardMove::new(Square::parse("c8").unwrap(), Square::parse("d7").unwrap())));
+    }
+
+    // Sets up a position with only the kings and rooks on their starting squares, and full castling rights.
+    fn castling_position() -> StandardPosition<MailboxBoard<ColoredStandardPiece>> {
+        let mut position = StandardPosition::starting_position();
+        for row in [0, 1, 6, 7] {
+            for column in 0..8 {
+                let square = Square::new(row, column);
+                let piece = position.board().at(square).unwrap();
+                if piece.piece() != StandardPiece::King && piece.piece() != StandardPiece::Rook {
+                    position.board_mut().set(square, None).unwrap();
+                }
+            }
+        }
+        position
+    }
+
+    #[test]
+    fn castling() {
+        let validator = StandardMoveValidator {};
+        let position = castling_position();
+        let e1 = Square::parse("e1").unwrap();
+        assert!(validator.validate(&position, &StandardMove::castle(e1, CastlingSide::KingSide).unwrap()));
+        assert!(validator.validate(&position, &StandardMove::castle(e1, CastlingSide::QueenSide).unwrap()));
+
+        // A bare board has no castling rights
+        assert!(!validator.validate(position.board(), &StandardMove::castle(e1, CastlingSide::KingSide).unwrap()));
+
+        // Castling rights lost
+        let mut lost = castling_position();
+        let mut rights = lost.castling_rights();
+        rights.remove(PieceColor::White, CastlingSide::KingSide);
+        lost.set_castling_rights(rights);
+        assert!(!validator.validate(&lost, &StandardMove::castle(e1, CastlingSide::KingSide).unwrap()));
+        assert!(validator.validate(&lost, &StandardMove::castle(e1, CastlingSide::QueenSide).unwrap()));
+
+        // The rook is missing
+        let mut missing = castling_position();
+        missing.board_mut().set(Square::parse("h1").unwrap(), None).unwrap();
+        assert!(!validator.validate(&missing, &StandardMove::castle(e1, CastlingSide::KingSide).unwrap()));
+
+        // A piece is in the way, even if only the rook has to move over it
+        let mut blocked = castling_position();
+        blocked.board_mut().set(Square::parse("b1").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Knight, PieceColor::White))).unwrap();
+        assert!(!validator.validate(&blocked, &StandardMove::castle(e1, CastlingSide::QueenSide).unwrap()));
+        assert!(validator.validate(&blocked, &StandardMove::castle(e1, CastlingSide::KingSide).unwrap()));
+    }
+
+    #[test]
+    fn castling_through_check() {
+        let validator = StandardMoveValidator {};
+        let e1 = Square::parse("e1").unwrap();
+        let place = |square: &str, piece: StandardPiece| {
+            let mut position = castling_position();
+            position.board_mut().set(Square::parse(square).unwrap(), Some(ColoredStandardPiece::new(piece, PieceColor::Black))).unwrap();
+            position
+        };
+
+        // Cannot castle out of check
+        let position = place("e4", StandardPiece::Rook);
+        assert!(!validator.validate(&position, &StandardMove::castle(e1, CastlingSide::KingSide).unwrap()));
+        assert!(!validator.validate(&position, &StandardMove::castle(e1, CastlingSide::QueenSide).unwrap()));
+
+        // Cannot castle through an attacked square
+        let position = place("f5", StandardPiece::Rook);
+        assert!(!validator.validate(&position, &StandardMove::castle(e1, CastlingSide::KingSide).unwrap()));
+        assert!(validator.validate(&position, &StandardMove::castle(e1, CastlingSide::QueenSide).unwrap()));
+
+        // Cannot castle into check
+        let position = place("c4", StandardPiece::Rook);
+        assert!(!validator.validate(&position, &StandardMove::castle(e1, CastlingSide::QueenSide).unwrap()));
+        assert!(validator.validate(&position, &StandardMove::castle(e1, CastlingSide::KingSide).unwrap()));
+
+        // The rook is allowed to pass over an attacked square
+        let position = place("b4", StandardPiece::Rook);
+        assert!(validator.validate(&position, &StandardMove::castle(e1, CastlingSide::QueenSide).unwrap()));
+    }
+
+    #[test]
+    fn chess960_castling_behind_rook() {
+        let validator = StandardMoveValidator {};
+        // The rook on b1 blocks the attack of the black rook on c1, until it moves away when castling.
+        let position = StandardPosition::from_fen("4k3/8/8/8/8/8/8/rR3K2 w B - 0 1").unwrap();
+        let castling = position.castling_move(PieceColor::White, CastlingSide::QueenSide).unwrap();
+        assert_eq!(castling, StandardMove::new(Square::parse("f1").unwrap(), Square::parse("c1").unwrap()));
+        assert!(!validator.validate(&position, &castling));
+
+        let position = StandardPosition::from_fen("4k3/8/8/8/8/8/8/nR3K2 w B - 0 1").unwrap();
+        assert!(validator.validate(&position, &castling));
+    }
+
+    #[test]
+    fn en_passant() {
+        let validator = StandardMoveValidator {};
+        let mut position = StandardPosition::starting_position();
+        for (from, to) in [("e2", "e4"), ("a7", "a6"), ("e4", "e5"), ("d7", "d5")] {
+            position.make_move(&StandardMove::new(Square::parse(from).unwrap(), Square::parse(to).unwrap())).unwrap();
+        }
+
+        // The pawn on d5 can be captured en passant, but only right now
+        let capture = StandardMove::new(Square::parse("e5").unwrap(), Square::parse("d6").unwrap());
+        assert!(validator.validate(&position, &capture));
+        // Only from a square next to the pawn
+        assert!(!validator.validate(&position, &StandardMove::new(Square::parse("c5").unwrap(), Square::parse("d6").unwrap())));
+        // And a bare board does not know about en passant at all
+        assert!(!validator.validate(position.board(), &capture));
+
+        let mut later = position.clone();
+        later.make_move(&StandardMove::new(Square::parse("h2").unwrap(), Square::parse("h3").unwrap())).unwrap();
+        later.make_move(&StandardMove::new(Square::parse("h7").unwrap(), Square::parse("h6").unwrap())).unwrap();
+        assert!(!validator.validate(&later, &capture));
+    }
+
+    #[test]
+    fn en_passant_discovered_check() {
+        // The capturing and captured pawn both leave the rank at the same time, exposing the king to the rook.
+        let validator = StandardMoveValidator {};
+        let mut position = StandardPosition::new(MailboxBoard::new(8, 8));
+        let board = position.board_mut();
+        board.set(Square::parse("a5").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::King, PieceColor::White))).unwrap();
+        board.set(Square::parse("d5").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::White))).unwrap();
+        board.set(Square::parse("e7").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::Black))).unwrap();
+        board.set(Square::parse("h5").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Rook, PieceColor::Black))).unwrap();
+        position.set_side_to_move(PieceColor::Black);
+        position.make_move(&StandardMove::new(Square::parse("e7").unwrap(), Square::parse("e5").unwrap())).unwrap();
+
+        assert_eq!(position.en_passant(), Some(Square::parse("e6").unwrap()));
+        assert!(!validator.validate(&position, &StandardMove::new(Square::parse("d5").unwrap(), Square::parse("e6").unwrap())));
+        assert!(validator.validate(&position, &StandardMove::new(Square::parse("d5").unwrap(), Square::parse("d6").unwrap())));
+    }
+
+    #[test]
+    fn promotion() {
+        let mut board = MailboxBoard::<ColoredStandardPiece>::new(8, 8);
+        let validator = StandardMoveValidator {};
+        board.set(Square::parse("b7").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::White))).unwrap();
+        board.set(Square::parse("e2").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::Black))).unwrap();
+        board.set(Square::parse("f1").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Rook, PieceColor::White))).unwrap();
+        board.set(Square::parse("c5").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::White))).unwrap();
+        board.set(Square::parse("g7").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Rook, PieceColor::White))).unwrap();
+        let b7 = Square::parse("b7").unwrap();
+        let b8 = Square::parse("b8").unwrap();
+
+        // A pawn reaching the last rank must promote
+        assert!(!validator.validate(&board, &StandardMove::new(b7, b8)));
+        for piece in [StandardPiece::Knight, StandardPiece::Bishop, StandardPiece::Rook, StandardPiece::Queen] {
+            assert!(validator.validate(&board, &StandardMove::promote(b7, b8, piece)));
+        }
+        // But not to a pawn or king
+        assert!(!validator.validate(&board, &StandardMove::promote(b7, b8, StandardPiece::Pawn)));
+        assert!(!validator.validate(&board, &StandardMove::promote(b7, b8, StandardPiece::King)));
+
+        // Black promotes on the first rank, also when capturing
+        let e2 = Square::parse("e2").unwrap();
+        assert!(validator.validate(&board, &StandardMove::promote(e2, Square::parse("e1").unwrap(), StandardPiece::Queen)));
+        assert!(validator.validate(&board, &StandardMove::promote(e2, Square::parse("f1").unwrap(), StandardPiece::Knight)));
+        assert!(!validator.validate(&board, &StandardMove::new(e2, Square::parse("f1").unwrap())));
+
+        // Promoting anywhere else is not allowed
+        assert!(!validator.validate(&board, &StandardMove::promote(Square::parse("c5").unwrap(), Square::parse("c6").unwrap(), StandardPiece::Queen)));
+        assert!(!validator.validate(&board, &StandardMove::promote(Square::parse("g7").unwrap(), Square::parse("g8").unwrap(), StandardPiece::Queen)));
+        assert!(validator.validate(&board, &StandardMove::new(Square::parse("g7").unwrap(), Square::parse("g8").unwrap())));
+    }
+
+    #[test]
+    fn promotion_on_larger_board() {
+        // The last rank depends on the height of the board
+        let mut board = MailboxBoard::<ColoredStandardPiece>::new(8, 10);
+        let validator = StandardMoveValidator {};
+        board.set(Square::parse("a7").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::White))).unwrap();
+        board.set(Square::parse("a9").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::White))).unwrap();
+        assert!(validator.validate(&board, &StandardMove::new(Square::parse("a7").unwrap(), Square::parse("a8").unwrap())));
+        assert!(!validator.validate(&board, &StandardMove::new(Square::parse("a9").unwrap(), Square::parse("a10").unwrap())));
+        assert!(validator.validate(&board, &StandardMove::promote(Square::parse("a9").unwrap(), Square::parse("a10").unwrap(), StandardPiece::Rook)));
+    }
+
+    #[test]
+    fn position_side_to_move() {
+        let mut position = StandardPosition::starting_position();
+        let validator = StandardMoveValidator {};
+        let e4 = StandardMove::new(Square::parse("e2").unwrap(), Square::parse("e4").unwrap());
+        let e5 = StandardMove::new(Square::parse("e7").unwrap(), Square::parse("e5").unwrap());
+
+        // Black cannot move first
+        assert!(!validator.validate(&position, &e5));
+        assert!(validator.validate(&position, &e4));
+        position.make_move(&e4).unwrap();
+        // Now it is black's turn
+        assert!(validator.validate(&position, &e5));
+        assert!(!validator.validate(&position, &StandardMove::new(Square::parse("d2").unwrap(), Square::parse("d4").unwrap())));
+    }
 }
\ No newline at end of file
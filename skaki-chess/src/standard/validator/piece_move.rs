@@ -8,14 +8,14 @@ pub fn is_pawn_move_illegal(color: PieceColor, board_height: u16, from: Square,
     // - A single step forward
     // - Two steps forward
     // First determine which direction is 'forward' for this color.
-    let forward: i16 = if color == PieceColor::White { 1 } else { -1 };
+    let forward: i32 = if color == PieceColor::White { 1 } else { -1 };
 
     // If the columns of the from and to squares are different, the only possibility left is a pawn capture
     if from.column != to.column {
         // This move is only legal if the rows differ by one unit in the forward direction,
         // and the columns differ by exactly one in any direction.
         // Since this function should return true if the move is illegal, the condition is inverted.
-        return u16::abs_diff(from.column, to.column) != 1|| from.row as i16 + forward != to.row as i16;
+        return from.offset(forward, -1) != Some(to) && from.offset(forward, 1) != Some(to);
     }
 
     // If the columns are the same, check if the pawn is on its starting location
@@ -24,14 +24,21 @@ pub fn is_pawn_move_illegal(color: PieceColor, board_height: u16, from: Square,
     if from.row == start_row {
         // Pawn is in its starting row. Can move at most two steps forward, so the move is illegal
         // if the destination row is not equal to either of those places.
-        return from.row as i16 + forward != to.row as i16 && from.row as i16 + 2 * forward != to.row as i16;
+        return from.offset(forward, 0) != Some(to) && from.offset(2 * forward, 0) != Some(to);
     }
 
     // Pawn is not on its starting location and did not make a capture, it can only move one space forward.
     // Since this function should return true if the move is illegal, the condition is inverted.
-    from.row as i16 + forward != to.row as i16
+    from.offset(forward, 0) != Some(to)
 }
 
+/// The row on which pawns of the given color promote. This is the last row in their forward direction.
+pub fn promotion_row(color: PieceColor, board_height: u16) -> u16 {
+    if color == PieceColor::White { board_height - 1 } else { 0 }
+}
+
+// The condition below spells out both knight patterns separately, which clippy would rather see merged.
+#[allow(clippy::nonminimal_bool)]
 pub fn is_knight_move_illegal(from: Square, to: Square) -> bool {
     // Knight moves are very simple. They are only legal if the rows differ by one and the columns by two,
     // or the other way around.
@@ -71,6 +78,27 @@ pub fn is_king_move_illegal(from: Square, to: Square) -> bool {
     row_diff > 1 || col_diff > 1
 }
 
+/// Returns true if any square strictly between `from` and `to` holds a token.
+/// This is only meaningful for moves along a straight line or a diagonal, for any other move (such as a knight jump)
+/// there are no squares in between and the path is never obstructed.
+pub fn is_path_obstructed<B: Board>(board: &B, from: Square, to: Square) -> bool {
+    let row_diff = u16::abs_diff(from.row, to.row);
+    let col_diff = u16::abs_diff(from.column, to.column);
+    // Only straight lines and diagonals have a well-defined path between the two squares.
+    if row_diff != 0 && col_diff != 0 && row_diff != col_diff {
+        return false;
+    }
+
+    // Determine the direction to step in. Each component is -1, 0 or 1.
+    let row_step = to.row.cmp(&from.row) as i32;
+    let col_step = to.column.cmp(&from.column) as i32;
+    let distance = row_diff.max(col_diff) as i32;
+    // Walk over every square in between, excluding the start and end squares themselves.
+    (1..distance)
+        .filter_map(|i| from.offset(i * row_step, i * col_step))
+        .any(|square| board.get(square).is_some())
+}
+
 /// Implements basic piece movement. Does not do any checking about
 /// the rest of the board. Does not make any verdict about whether the move is actually allowed.
 /// This method can only be used to fast-fail things like bishops moving straight, or knights jumping across the board.
@@ -98,7 +126,7 @@ pub fn is_movement_illegal<B: Board<Token = ColoredStandardPiece>>(piece: Colore
             is_queen_move_illegal(from, to)
         }
         StandardPiece::King => {
-            true
+            is_king_move_illegal(from, to)
         }
     }
 }
@@ -106,6 +134,7 @@ pub fn is_movement_illegal<B: Board<Token = ColoredStandardPiece>>(piece: Colore
 
 #[cfg(test)]
 mod test {
+    use crate::board::mailbox::MailboxBoard;
     use super::*;
 
     #[test]
@@ -155,6 +184,29 @@ mod test {
         assert!(!is_pawn_move_illegal(PieceColor::Black, 8, Square::parse("f4").unwrap(), Square::parse("f3").unwrap()));
     }
 
+    #[test]
+    fn path_obstruction() {
+        let mut board = MailboxBoard::<ColoredStandardPiece>::new(8, 8);
+        board.set(Square::parse("a2").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::White))).unwrap();
+        board.set(Square::parse("d4").unwrap(), Some(ColoredStandardPiece::new(StandardPiece::Knight, PieceColor::Black))).unwrap();
+
+        // Straight lines through an occupied square
+        assert!(is_path_obstructed(&board, Square::parse("a1").unwrap(), Square::parse("a8").unwrap()));
+        assert!(is_path_obstructed(&board, Square::parse("a1").unwrap(), Square::parse("a3").unwrap()));
+        // Diagonal through an occupied square
+        assert!(is_path_obstructed(&board, Square::parse("b2").unwrap(), Square::parse("f6").unwrap()));
+        assert!(is_path_obstructed(&board, Square::parse("g7").unwrap(), Square::parse("a1").unwrap()));
+        // The start and end squares themselves do not count as obstructions
+        assert!(!is_path_obstructed(&board, Square::parse("a2").unwrap(), Square::parse("a8").unwrap()));
+        assert!(!is_path_obstructed(&board, Square::parse("a1").unwrap(), Square::parse("a2").unwrap()));
+        assert!(!is_path_obstructed(&board, Square::parse("b6").unwrap(), Square::parse("d4").unwrap()));
+        // Paths that do not cross any tokens
+        assert!(!is_path_obstructed(&board, Square::parse("h1").unwrap(), Square::parse("h8").unwrap()));
+        assert!(!is_path_obstructed(&board, Square::parse("c1").unwrap(), Square::parse("h6").unwrap()));
+        // Knight jumps never have an obstructed path
+        assert!(!is_path_obstructed(&board, Square::parse("c3").unwrap(), Square::parse("e4").unwrap()));
+    }
+
     #[test]
     fn test_knight_moves() {
         assert!(is_knight_move_illegal(Square::parse("g1").unwrap(), Square::parse("g2").unwrap()));
@@ -0,0 +1,325 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::OnceLock;
+use anyhow::{ensure, Context};
+use crate::board::Board;
+use crate::square::Square;
+use crate::standard::piece::{ColoredStandardPiece, PieceColor, StandardPiece};
+use crate::standard::position::{CastlingSide, StandardPosition};
+
+/// The number of random keys in the Polyglot layout.
+pub const POLYGLOT_KEY_COUNT: usize = 781;
+
+// Offsets of the different kinds of keys in the Polyglot layout.
+const CASTLING_OFFSET: usize = 768;
+const EN_PASSANT_OFFSET: usize = 772;
+const TURN_OFFSET: usize = 780;
+
+/// The seed used for the keys returned by `ZobristKeys::default_keys`.
+const DEFAULT_SEED: u64 = 0x7a6f_6272_6973_7431;
+
+/// The random numbers used for Zobrist hashing positions.
+///
+/// A position is hashed by combining, with xor, the keys of every piece on its square, every castling right, the file
+/// of the en passant square and whether white is to move. The keys are stored in the same layout as Polyglot opening
+/// books use, so a position hashes to its Polyglot book key when the keys are created from the Polyglot `Random64`
+/// table with `from_polyglot` or `parse_polyglot`. The table itself is not included in this crate:
+/// - Pieces use keys `64 * kind + 8 * row + column`, where the kind is 0 for a black pawn, 1 for a white pawn, 2 for
+///   a black knight and so on, up to 11 for a white king.
+/// - White king side, white queen side, black king side and black queen side castling use keys 768 to 771.
+/// - The en passant file uses keys 772 to 779, but only if a pawn of the side to move stands next to the pawn that
+///   just made a double step. Whether that capture is actually legal does not matter.
+/// - Key 780 is used when white is to move.
+///
+/// Pieces and en passant files outside a regular 8x8 board get keys derived from the table, so larger boards can be
+/// hashed as well.
+#[derive(Clone)]
+pub struct ZobristKeys {
+    keys: [u64; POLYGLOT_KEY_COUNT],
+}
+
+impl ZobristKeys {
+    /// Uses the given keys, in the Polyglot layout described above.
+    pub fn from_polyglot(keys: [u64; POLYGLOT_KEY_COUNT]) -> Self {
+        Self { keys }
+    }
+
+    /// Reads the keys from the text of a table in the Polyglot layout, such as the `Random64` array in the source code
+    /// of Polyglot or in its book format description. Every number written in hexadecimal with a `0x` prefix is read
+    /// as the next key, anything else in between (like commas or a `U64(...)` wrapper) is ignored.
+    ///
+    /// Returns Err(_) if a number is not a valid 64 bit key, or the text does not contain exactly 781 of them.
+    pub fn parse_polyglot(table: &str) -> anyhow::Result<Self> {
+        let keys = table.split(|c: char| !c.is_ascii_alphanumeric())
+            .filter_map(|token| token.strip_prefix("0x").or_else(|| token.strip_prefix("0X")))
+            .map(|digits| {
+                // C sources sometimes add a suffix like `ULL` to the number.
+                let digits = digits.trim_end_matches(['u', 'U', 'l', 'L']);
+                u64::from_str_radix(digits, 16).with_context(|| format!("Invalid key '0x{digits}'"))
+            })
+            .collect::<anyhow::Result<Vec<u64>>>()?;
+        ensure!(keys.len() == POLYGLOT_KEY_COUNT, "Expected {POLYGLOT_KEY_COUNT} keys, but found {}", keys.len());
+        Ok(Self::from_polyglot(keys.try_into().expect("Length was checked")))
+    }
+
+    /// Generates random keys from a seed. The same seed always results in the same keys.
+    pub fn generate(seed: u64) -> Self {
+        let mut state = seed;
+        Self { keys: std::array::from_fn(|_| split_mix(&mut state)) }
+    }
+
+    /// The keys used by positions unless set otherwise, generated from a fixed seed. These are not the Polyglot keys,
+    /// so hashes made with them cannot be used to look up positions in Polyglot opening books.
+    pub fn default_keys() -> &'static Self {
+        static KEYS: OnceLock<ZobristKeys> = OnceLock::new();
+        KEYS.get_or_init(|| Self::generate(DEFAULT_SEED))
+    }
+
+    /// The key for a piece standing on a square.
+    pub fn piece(&self, piece: &ColoredStandardPiece, square: Square) -> u64 {
+        let kind = piece_kind(piece);
+        if square.row < 8 && square.column < 8 {
+            return self.keys[64 * kind + 8 * square.row as usize + square.column as usize];
+        }
+        self.derived(((kind as u64) << 32) | ((square.row as u64) << 16) | square.column as u64)
+    }
+
+    /// The key for a castling right.
+    pub fn castling(&self, color: PieceColor, side: CastlingSide) -> u64 {
+        let index = match (color, side) {
+            (PieceColor::White, CastlingSide::KingSide) => 0,
+            (PieceColor::White, CastlingSide::QueenSide) => 1,
+            (PieceColor::Black, CastlingSide::KingSide) => 2,
+            (PieceColor::Black, CastlingSide::QueenSide) => 3,
+        };
+        self.keys[CASTLING_OFFSET + index]
+    }
+
+    /// The key for an en passant square on the given file.
+    pub fn en_passant(&self, column: u16) -> u64 {
+        if column < 8 {
+            return self.keys[EN_PASSANT_OFFSET + column as usize];
+        }
+        self.derived((1 << 48) | column as u64)
+    }
+
+    /// The key used when white is to move.
+    pub fn white_to_move(&self) -> u64 {
+        self.keys[TURN_OFFSET]
+    }
+
+    /// Computes the hash of a position from scratch.
+    /// Positions keep their hash up to date as moves are made, see `StandardPosition::hash`.
+    pub fn hash<B: Board<Token = ColoredStandardPiece>>(&self, position: &StandardPosition<B>) -> u64 {
+        position.board().iter().fold(self.state(position), |hash, (square, piece)| hash ^ self.piece(piece, square))
+    }
+
+    /// The part of the hash that does not depend on the pieces: castling rights, en passant file and side to move.
+    pub(crate) fn state<B: Board<Token = ColoredStandardPiece>>(&self, position: &StandardPosition<B>) -> u64 {
+        let mut hash = 0;
+        for color in [PieceColor::White, PieceColor::Black] {
+            for side in [CastlingSide::KingSide, CastlingSide::QueenSide] {
+                if position.castling_rights().has(color, side) {
+                    hash ^= self.castling(color, side);
+                }
+            }
+        }
+
+        if let Some(square) = position.en_passant() && can_capture_en_passant(position, square) {
+            hash ^= self.en_passant(square.column);
+        }
+
+        if position.side_to_move() == PieceColor::White {
+            hash ^= self.white_to_move();
+        }
+        hash
+    }
+
+    // A key for something that has no place in the table, mixed with the first key so it depends on the table too.
+    fn derived(&self, id: u64) -> u64 {
+        let mut state = id ^ self.keys[0];
+        split_mix(&mut state)
+    }
+}
+
+impl Debug for ZobristKeys {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ZobristKeys").finish_non_exhaustive()
+    }
+}
+
+// The Polyglot piece kind: black pieces are even, white pieces odd, ordered pawn, knight, bishop, rook, queen, king.
+fn piece_kind(piece: &ColoredStandardPiece) -> usize {
+    let kind = match piece.piece() {
+        StandardPiece::Pawn => 0,
+        StandardPiece::Knight => 1,
+        StandardPiece::Bishop => 2,
+        StandardPiece::Rook => 3,
+        StandardPiece::Queen => 4,
+        StandardPiece::King => 5,
+    };
+    2 * kind + (piece.color() == PieceColor::White) as usize
+}
+
+// Whether a pawn of the side to move stands next to the pawn that just made a double step.
+fn can_capture_en_passant<B: Board<Token = ColoredStandardPiece>>(position: &StandardPosition<B>, square: Square) -> bool {
+    let color = position.side_to_move();
+    let forward = if color == PieceColor::White { 1 } else { -1 };
+    let pawn = ColoredStandardPiece::new(StandardPiece::Pawn, color);
+    [-1, 1].into_iter()
+        .filter_map(|columns| square.offset(-forward, columns))
+        .any(|capturer| position.board().get(capturer) == Some(&pawn))
+}
+
+// The SplitMix64 generator, which turns any seed into well distributed numbers.
+fn split_mix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::mailbox::MailboxBoard;
+    use crate::generator::MoveGenerator;
+    use crate::standard::generator::StandardMoveGenerator;
+    use crate::standard::moves::StandardMove;
+    use crate::standard::position::CastlingRights;
+    use super::*;
+
+    type Position = StandardPosition<MailboxBoard<ColoredStandardPiece>>;
+
+    fn play(position: &mut Position, moves: &[&str]) {
+        for mov in moves {
+            let mov = position.parse_uci(mov).unwrap();
+            position.make_move(&mov).unwrap();
+        }
+    }
+
+    // Keys that are simply their own index plus one, which makes it easy to check the layout.
+    fn index_keys() -> &'static ZobristKeys {
+        static KEYS: OnceLock<ZobristKeys> = OnceLock::new();
+        KEYS.get_or_init(|| ZobristKeys::from_polyglot(std::array::from_fn(|index| index as u64 + 1)))
+    }
+
+    #[test]
+    fn polyglot_layout() {
+        let keys = index_keys();
+        let square = |name: &str| Square::parse(name).unwrap();
+        assert_eq!(keys.piece(&ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::Black), square("a1")), 1);
+        assert_eq!(keys.piece(&ColoredStandardPiece::new(StandardPiece::Pawn, PieceColor::White), square("a1")), 65);
+        assert_eq!(keys.piece(&ColoredStandardPiece::new(StandardPiece::King, PieceColor::White), square("e1")), 64 * 11 + 4 + 1);
+        assert_eq!(keys.piece(&ColoredStandardPiece::new(StandardPiece::Knight, PieceColor::Black), square("g8")), 64 * 2 + 8 * 7 + 6 + 1);
+        assert_eq!(keys.castling(PieceColor::White, CastlingSide::KingSide), 769);
+        assert_eq!(keys.castling(PieceColor::Black, CastlingSide::QueenSide), 772);
+        assert_eq!(keys.en_passant(0), 773);
+        assert_eq!(keys.en_passant(7), 780);
+        assert_eq!(keys.white_to_move(), 781);
+    }
+
+    #[test]
+    fn parse_polyglot_table() {
+        let table: String = (1..=POLYGLOT_KEY_COUNT as u64).map(|key| format!("   U64(0x{key:016X}),\n")).collect();
+        let keys = ZobristKeys::parse_polyglot(&format!("const U64 Random64[781] = {{\n{table}}};")).unwrap();
+        let position = Position::starting_position();
+        assert_eq!(keys.hash(&position), index_keys().hash(&position));
+        assert_eq!(keys.white_to_move(), 781);
+
+        assert!(ZobristKeys::parse_polyglot("0x1, 0x2").is_err());
+        assert!(ZobristKeys::parse_polyglot(&format!("{table}, 0x1")).is_err());
+        assert!(ZobristKeys::parse_polyglot(&table.replace("0x0000000000000001", "0x0000000000000001G")).is_err());
+        assert!(ZobristKeys::parse_polyglot(&table.replace("0x0000000000000001", "0x10000000000000000")).is_err());
+    }
+
+    #[test]
+    #[ignore = "needs the Polyglot Random64 table, set POLYGLOT_RANDOM64 to a file containing it"]
+    fn polyglot_reference_keys() {
+        let path = std::env::var("POLYGLOT_RANDOM64").expect("POLYGLOT_RANDOM64 is not set");
+        let table = std::fs::read_to_string(path).unwrap();
+        let keys = ZobristKeys::parse_polyglot(&table).unwrap();
+
+        // The example keys from the Polyglot book format description
+        let mut position = Position::starting_position();
+        assert_eq!(keys.hash(&position), 0x463b96181691fc9c);
+        play(&mut position, &["e2e4"]);
+        assert_eq!(keys.hash(&position), 0x823c9b50fd114196);
+    }
+
+    #[test]
+    fn en_passant_only_with_adjacent_pawn() {
+        let keys = ZobristKeys::default_keys();
+        // After 1. e4 no black pawn can capture, so the en passant square does not count.
+        let mut position = Position::starting_position();
+        play(&mut position, &["e2e4"]);
+        let mut without = position.clone();
+        without.set_en_passant(None);
+        assert_eq!(keys.hash(&position), keys.hash(&without));
+
+        // After 1. e4 d5 2. e5 f5 the pawn on e5 can capture, even if it would not be legal.
+        play(&mut position, &["d7d5", "e4e5", "f7f5"]);
+        let mut without = position.clone();
+        without.set_en_passant(None);
+        assert_ne!(keys.hash(&position), keys.hash(&without));
+    }
+
+    #[test]
+    fn transpositions() {
+        let mut first = Position::starting_position();
+        play(&mut first, &["g1f3", "g8f6", "b1c3", "b8c6"]);
+        let mut second = Position::starting_position();
+        play(&mut second, &["b1c3", "b8c6", "g1f3", "g8f6"]);
+        assert_eq!(first.hash(), second.hash());
+
+        // The same pieces, but without castling rights
+        let mut third = first.clone();
+        third.set_castling_rights(CastlingRights::none());
+        assert_ne!(first.hash(), third.hash());
+        third.set_castling_rights(first.castling_rights());
+        assert_eq!(first.hash(), third.hash());
+    }
+
+    // Plays and takes back every legal move, checking the incremental hash against a full computation.
+    fn assert_incremental(position: &mut Position, depth: u32) {
+        if depth == 0 {
+            return;
+        }
+        let before = position.hash();
+        let generator = StandardMoveGenerator {};
+        for mov in generator.generate(position) {
+            let undo = position.make(&mov).unwrap();
+            assert_eq!(position.hash(), position.zobrist_keys().hash(position), "after {mov}");
+            assert_incremental(position, depth - 1);
+            position.unmake(undo).unwrap();
+            assert_eq!(position.hash(), before, "after taking back {mov}");
+        }
+    }
+
+    #[test]
+    fn incremental_hash() {
+        for fen in [
+            crate::standard::fen::STARTING_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "1r4kr/8/8/8/8/8/8/1R4KR w HBhb - 0 1",
+            "rnbqkbnrbn/pppppppppp/10/10/10/10/PPPPPPPPPP/RNBQKBNRBN w - - 0 1",
+        ] {
+            let mut position = StandardPosition::from_fen(fen).unwrap();
+            assert_eq!(position.hash(), ZobristKeys::default_keys().hash(&position));
+            assert_incremental(&mut position, 2);
+        }
+    }
+
+    #[test]
+    fn changing_keys_or_board() {
+        let mut position = Position::starting_position();
+        position.set_zobrist_keys(index_keys());
+        assert_eq!(position.hash(), index_keys().hash(&position));
+
+        position.board_mut().set(Square::parse("e2").unwrap(), None).unwrap();
+        assert_eq!(position.hash(), index_keys().hash(&position));
+        position.make_move(&StandardMove::new(Square::parse("d2").unwrap(), Square::parse("d4").unwrap())).unwrap();
+        assert_eq!(position.hash(), index_keys().hash(&position));
+    }
+}
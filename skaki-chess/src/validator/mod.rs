@@ -1,10 +1,12 @@
-use crate::board::Board;
 use crate::moves::Move;
 
 /// Represents an algorithm to validate whether a move is legal.
 /// This can vary from variant to variant, so it is a trait we can swap out
-pub trait MoveValidator<B: Board, M: Move> {
-    /// Returns true if making the move on the board is legal, according to the rules
+///
+/// The position `P` is whatever state the validator needs to make its decision. For very simple rulesets this
+/// can be a bare board, but most variants also need to know things like whose turn it is.
+pub trait MoveValidator<P, M: Move> {
+    /// Returns true if making the move in the given position is legal, according to the rules
     /// of this move validator.
-    fn validate(&self, board: &B, mov: &M) -> bool;
+    fn validate(&self, position: &P, mov: &M) -> bool;
 }